@@ -0,0 +1,94 @@
+//! Times decoding a `getRouterStatus` response and running every collector over it, which is a
+//! scrape less the request to the hub. Run with `cargo bench --bench router_status` to check a
+//! change on a low-power host such as a Raspberry Pi.
+
+use std::{collections::HashMap, hint::black_box, time::Instant};
+use virgin_media_prometheus_exporter::{
+    client::VirginHubClient,
+    collector::Collector,
+    oid,
+    settings::Settings,
+    snmp::{List, OID},
+};
+
+const ARRIS_CM_DOC30_SW_REGISTRATION_STATE: OID = oid!("1.3.6.1.4.1.4115.1.3.4.1.5.9"); // arrisCmDoc30SwRegistrationState
+const DOCSIS_BASE_CAPABILITY: OID = oid!("1.3.6.1.2.1.10.127.1.1.5"); // DocsisBaseCapability
+
+/// A response shaped like a DOCSIS 3.1 hub's, with 32 downstream and 5 upstream channels, 40
+/// interfaces, 200 LAN clients and a full event log, plus other values the collectors don't read.
+fn router_status() -> String {
+    let mut values = HashMap::new();
+    let mut table = |oid: &str, indices: &[String], columns: u32| {
+        for (row, index) in (1..).zip(indices) {
+            for column in 1..=columns {
+                values.insert(format!("{}.1.{}.{}", oid, column, index), row.to_string());
+            }
+        }
+    };
+    let rows = |count: u32| (1..=count).map(|row| row.to_string()).collect::<Vec<_>>();
+    let flows = (1..=8)
+        .map(|sfid| format!("2.{}", sfid))
+        .collect::<Vec<_>>();
+    let lan_clients = (1..=200)
+        .map(|client| format!("200.1.4.192.168.0.{}", client))
+        .collect::<Vec<_>>();
+
+    table("1.3.6.1.2.1.10.127.1.1.1", &rows(32), 9); // docsIfDownstreamChannelTable
+    table("1.3.6.1.2.1.10.127.1.1.4", &rows(32), 7); // docsIfSignalQualityTable
+    table("1.3.6.1.4.1.4491.2.1.20.1.24", &rows(32), 4); // docsIf3SignalQualityExtTable
+    table("1.3.6.1.2.1.10.127.1.1.2", &rows(5), 25); // docsIfUpstreamChannelTable
+    table("1.3.6.1.4.1.4115.1.3.4.1.9.2", &rows(5), 3); // arrisCmDoc30IfUpstreamChannelExtendedTable
+    table("1.3.6.1.4.1.4491.2.1.20.1.2", &rows(5), 10); // docsIf3CmStatusUsTable
+    table("1.3.6.1.4.1.4491.2.1.21.1.3", &flows, 10); // docsQosServiceFlowTable
+    table("1.3.6.1.4.1.4491.2.1.21.1.2", &flows, 22); // docsQosParamSetTable
+    table("1.3.6.1.4.1.4491.2.1.21.1.4", &flows, 7); // docsQosServiceFlowStatsTable
+    table("1.3.6.1.2.1.2.2", &rows(40), 22); // ifTable
+    table("1.3.6.1.2.1.31.1.1", &rows(40), 19); // ifXTable
+    table("1.3.6.1.2.1.69.1.5.8", &rows(64), 7); // docsDevEventTable
+    table("1.3.6.1.4.1.4115.1.20.1.1.2.4.2", &lan_clients, 14); // arrisRouterLanClientTable
+
+    values.insert(
+        format!("{}.0", ARRIS_CM_DOC30_SW_REGISTRATION_STATE),
+        "12".to_owned(),
+    );
+    values.insert(format!("{}.0", DOCSIS_BASE_CAPABILITY), "5".to_owned());
+    values.insert("1".to_owned(), "Finish".to_owned());
+
+    serde_json::to_string(&values).unwrap()
+}
+
+fn bench(name: &str, iterations: u32, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let elapsed = start.elapsed() / iterations;
+    println!("{:<24} {:>10.1} µs/iter", name, elapsed.as_secs_f64() * 1e6);
+}
+
+fn main() {
+    let response = router_status();
+
+    let settings: Settings =
+        serde_json::from_str(r#"{"hub_ip": "192.168.100.1", "port": 9000, "mta": true}"#).unwrap();
+    let client = VirginHubClient::new(settings.hub_ip, settings.max_response_size);
+    let collector = Collector::new(client, &settings).unwrap();
+    let subtrees = collector.subtrees();
+
+    bench("decode", 100, || {
+        black_box(List::from_reader(response.as_bytes(), subtrees).unwrap());
+    });
+
+    // Each run needs a fresh list, as parsed tables are cached on it
+    let mut lists = (0..100)
+        .map(|_| List::from_reader(response.as_bytes(), subtrees).unwrap())
+        .collect::<Vec<_>>();
+    bench("collect", 100, || {
+        collector.set(&lists.pop().unwrap()).unwrap();
+    });
+
+    bench("scrape", 100, || {
+        let list = List::from_reader(response.as_bytes(), subtrees).unwrap();
+        collector.set(&list).unwrap();
+    });
+}
@@ -0,0 +1,350 @@
+//! Derive macros for decoding the exporter's SNMP table rows and enumerations.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    ext::IdentExt,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Attribute, Data, DeriveInput, Error, Expr, ExprLit, Fields, GenericArgument, Ident, Lit,
+    LitInt, LitStr, Path, PathArguments, Result, Token, Type,
+};
+
+/// A single `key = value` argument of an `#[snmp(...)]` attribute.
+enum Arg {
+    Column(Path),
+    Kind(Ident),
+    Scale(LitInt),
+    Name(LitStr),
+    Label(LitStr),
+    Crate(Path),
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> Result<Self> {
+        // `crate` is a keyword, so it has to be parsed as any identifier
+        let key = input.call(Ident::parse_any)?;
+        input.parse::<Token![=]>()?;
+
+        Ok(match key.to_string().as_str() {
+            "column" => Arg::Column(input.parse()?),
+            "kind" => Arg::Kind(input.parse()?),
+            "scale" => Arg::Scale(input.parse()?),
+            "name" => Arg::Name(input.parse()?),
+            "label" => Arg::Label(input.parse()?),
+            "crate" => Arg::Crate(input.parse::<LitStr>()?.parse()?),
+            _ => return Err(Error::new(key.span(), "unknown snmp attribute")),
+        })
+    }
+}
+
+fn parse_args(attrs: &[Attribute]) -> Result<Vec<Arg>> {
+    let mut args = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("snmp")) {
+        args.extend(attr.parse_args_with(Punctuated::<Arg, Token![,]>::parse_terminated)?);
+    }
+    Ok(args)
+}
+
+/// The path to the `snmp` module the generated code refers to, which is `crate::snmp` unless the
+/// type sets `#[snmp(crate = "...")]`, e.g. to derive outside the exporter's own crate.
+fn snmp_module(krate: Option<Path>) -> TokenStream2 {
+    match krate {
+        Some(krate) => quote!(#krate::snmp),
+        None => quote!(crate::snmp),
+    }
+}
+
+/// The `T` of an `Option<T>` field, which marks the column as optional.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Implements `TryFrom<TableEntry>` for a struct with named fields, each of which is read from
+/// the column given by its `#[snmp(column = ...)]` attribute.
+///
+/// By default a column is parsed into the field's type with `FromStr`. `kind = integer` (or any
+/// other SMI type with a `parse_<kind>` accessor on `TableEntry`) decodes it as that type first,
+/// and `scale = 10` divides the decoded value, e.g. for columns in tenths of a dB. `Option`
+/// fields are read with the `parse_optional_*` accessor, so the column may be missing.
+///
+/// Outside the exporter's crate, `#[snmp(crate = "...")]` on the struct names the crate to find
+/// the `snmp` module in.
+#[proc_macro_derive(TableRow, attributes(snmp))]
+pub fn derive_table_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    table_row(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn table_row(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(input, "TableRow needs named fields")),
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "TableRow can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut krate = None;
+    for arg in parse_args(&input.attrs)? {
+        match arg {
+            Arg::Crate(path) => krate = Some(path),
+            _ => {
+                return Err(Error::new_spanned(
+                    input,
+                    "only crate applies to table rows",
+                ))
+            }
+        }
+    }
+    let snmp = snmp_module(krate);
+
+    let fields = fields
+        .iter()
+        .map(|field| {
+            let ident = field.ident.as_ref().unwrap();
+
+            let (mut column, mut kind, mut scale) = (None, None, None);
+            for arg in parse_args(&field.attrs)? {
+                match arg {
+                    Arg::Column(path) => column = Some(path),
+                    Arg::Kind(ident) => kind = Some(ident),
+                    Arg::Scale(lit) => scale = Some(lit),
+                    Arg::Name(lit) | Arg::Label(lit) => {
+                        return Err(Error::new(lit.span(), "only applies to enumerations"))
+                    }
+                    Arg::Crate(path) => {
+                        return Err(Error::new_spanned(path, "only applies to the table row"))
+                    }
+                }
+            }
+            let column = column.ok_or_else(|| {
+                Error::new_spanned(ident, "missing #[snmp(column = ...)] attribute")
+            })?;
+
+            let inner = option_inner(&field.ty);
+            let value_ty = inner.unwrap_or(&field.ty);
+            let method = match (&kind, inner.is_some()) {
+                (None, false) => quote!(parse_column::<#value_ty>),
+                (None, true) => quote!(parse_optional_column::<#value_ty>),
+                (Some(kind), false) => format_ident!("parse_{}", kind).into_token_stream(),
+                (Some(kind), true) => format_ident!("parse_optional_{}", kind).into_token_stream(),
+            };
+
+            let convert = match &scale {
+                Some(scale) => quote!(#snmp::Scale::scale(value, #scale)),
+                None => quote!(::std::convert::Into::into(value)),
+            };
+            let value = if inner.is_some() {
+                quote!(entry.#method(&#column)?.map(|value| #convert))
+            } else {
+                quote!({
+                    let value = entry.#method(&#column)?;
+                    #convert
+                })
+            };
+
+            Ok(quote!(#ident: #value))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        impl ::std::convert::TryFrom<#snmp::TableEntry> for #name {
+            type Error = ::anyhow::Error;
+
+            fn try_from(
+                entry: #snmp::TableEntry,
+            ) -> ::std::result::Result<Self, Self::Error> {
+                ::std::result::Result::Ok(#name {
+                    #(#fields,)*
+                })
+            }
+        }
+    })
+}
+
+/// The default label for an enumeration value, e.g. `auth_reject_wait` for `AuthRejectWait` and
+/// `qam256` for `QAM256`. A capital followed by digits stays with the word before it, so
+/// `DhcpV4Complete` is `dhcpv4_complete`.
+fn snake_case(ident: &Ident) -> String {
+    let ident = ident.to_string();
+    let mut label = String::new();
+    let mut chars = ident.chars().peekable();
+    let mut previous: Option<char> = None;
+    while let Some(c) = chars.next() {
+        let next_is_digit = chars.peek().is_some_and(|next| next.is_ascii_digit());
+        if c.is_uppercase()
+            && !next_is_digit
+            && matches!(previous, Some(p) if p.is_lowercase() || p.is_numeric())
+        {
+            label.push('_');
+        }
+        label.extend(c.to_lowercase());
+        previous = Some(c);
+    }
+    label
+}
+
+/// Implements `Enumeration` for a fieldless enum from its explicit discriminants, which are the
+/// values of the SNMP enumeration. `#[snmp(name = "...")]` sets the syntax name used in errors,
+/// and defaults to the enum's name. Each value is labelled in snake case unless it has a
+/// `#[snmp(label = "...")]` attribute. As for table rows, `#[snmp(crate = "...")]` names the crate
+/// to find the `snmp` module in.
+#[proc_macro_derive(Enumeration, attributes(snmp))]
+pub fn derive_enumeration(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    enumeration(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn enumeration(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "Enumeration can only be derived for enums",
+            ))
+        }
+    };
+
+    let mut syntax = LitStr::new(&name.to_string(), Span::call_site());
+    let mut krate = None;
+    for arg in parse_args(&input.attrs)? {
+        match arg {
+            Arg::Name(lit) => syntax = lit,
+            Arg::Crate(path) => krate = Some(path),
+            _ => {
+                return Err(Error::new_spanned(
+                    input,
+                    "only name and crate apply to enumerations",
+                ))
+            }
+        }
+    }
+    let snmp = snmp_module(krate);
+
+    let mut codes = Vec::new();
+    let mut idents = Vec::new();
+    let mut labels = Vec::new();
+    for variant in variants {
+        let ident = &variant.ident;
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "enumeration values can't have fields",
+            ));
+        }
+
+        let code = match &variant.discriminant {
+            Some((
+                _,
+                Expr::Lit(ExprLit {
+                    lit: Lit::Int(code),
+                    ..
+                }),
+            )) => code.base10_parse::<i64>()?,
+            _ => {
+                return Err(Error::new_spanned(
+                    variant,
+                    "enumeration values need an integer discriminant",
+                ))
+            }
+        };
+
+        let mut label = LitStr::new(&snake_case(ident), ident.span());
+        for arg in parse_args(&variant.attrs)? {
+            match arg {
+                Arg::Label(lit) => label = lit,
+                _ => {
+                    return Err(Error::new_spanned(
+                        variant,
+                        "only label applies to enumeration values",
+                    ))
+                }
+            }
+        }
+
+        codes.push(code);
+        idents.push(ident);
+        labels.push(label);
+    }
+
+    Ok(quote! {
+        impl #snmp::Enumeration for #name {
+            const NAME: &'static str = #syntax;
+            const LABELS: &'static [&'static str] = &[#(#labels),*];
+
+            fn from_code(code: i64) -> ::std::option::Option<Self> {
+                match code {
+                    #(#codes => ::std::option::Option::Some(#name::#idents),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn code(self) -> i64 {
+                self as i64
+            }
+
+            fn label(self) -> &'static str {
+                match self {
+                    #(#name::#idents => #labels,)*
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(ident: &str) -> String {
+        snake_case(&Ident::new(ident, Span::call_site()))
+    }
+
+    #[test]
+    fn snake_case_words() {
+        assert_eq!(label("Start"), "start");
+        assert_eq!(label("AuthRejectWait"), "auth_reject_wait");
+    }
+
+    #[test]
+    fn snake_case_acronyms() {
+        assert_eq!(label("QAM256"), "qam256");
+        assert_eq!(label("ATDMA"), "atdma");
+    }
+
+    #[test]
+    fn snake_case_digit_runs() {
+        assert_eq!(label("DhcpV4Complete"), "dhcpv4_complete");
+        assert_eq!(label("DhcpV6InProgress"), "dhcpv6_in_progress");
+        assert_eq!(label("Band5GHz"), "band5_ghz");
+    }
+}
@@ -1,6 +1,6 @@
 use crate::client::VirginHubClient;
 use crate::collector::Collector;
-use crate::routes::{health_check, metrics};
+use crate::routes::{events, health_check, metrics};
 use crate::settings::Settings;
 
 use actix_web::dev::Server;
@@ -21,13 +21,14 @@ pub struct Application {
 
 impl Application {
     pub fn build(settings: Settings) -> Result<Self> {
-        let client = VirginHubClient::new(settings.hub_ip);
+        let client = VirginHubClient::new(settings.hub_ip, settings.max_response_size);
 
-        let collector = Collector::new(client)?;
+        let collector = Collector::new(client.clone(), &settings)?;
         let registry = Registry::new_custom(Some(String::from(REGISTRY_PREFIX)), None)?;
         registry.register(Box::new(collector))?;
 
         let registry = Data::new(registry);
+        let client = Data::new(client);
 
         let address = format!("0.0.0.0:{}", settings.port);
         let listener = TcpListener::bind(address)?;
@@ -38,7 +39,9 @@ impl Application {
                 .wrap(middleware::Logger::default())
                 .route("/health", web::get().to(health_check))
                 .route("/metrics", web::get().to(metrics))
+                .route("/events", web::get().to(events))
                 .app_data(registry.clone())
+                .app_data(client.clone())
         })
         .listen(listener)?
         .run();
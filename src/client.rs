@@ -1,28 +1,112 @@
-use crate::snmp::List;
+use crate::snmp::{List, OID};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use reqwest::blocking::Client;
-use std::net::IpAddr;
+use std::{
+    convert::TryFrom,
+    io::{self, BufReader, Read},
+    net::IpAddr,
+};
 
+#[derive(Clone)]
 pub struct VirginHubClient {
     client: Client,
     hub_ip: IpAddr,
+    max_response_size: u64,
 }
 
 impl VirginHubClient {
-    pub fn new(hub_ip: IpAddr) -> Self {
+    pub fn new(hub_ip: IpAddr, max_response_size: u64) -> Self {
         VirginHubClient {
             client: Client::new(),
             hub_ip,
+            max_response_size,
         }
     }
 
-    pub fn get_router_status(&self) -> Result<List> {
+    /// Fetches the router status, keeping only the values under `subtrees`.
+    pub fn get_router_status(&self, subtrees: &[OID]) -> Result<List> {
         let url = format!("http://{}/getRouterStatus", self.hub_ip);
 
         let response = self.client.get(&url).send()?;
 
-        let router_status: List = response.json()?;
-        Ok(router_status)
+        if let Some(length) = response.content_length() {
+            if length > self.max_response_size {
+                bail!(
+                    "router status response is {} bytes, more than max_response_size of {}",
+                    length,
+                    self.max_response_size
+                );
+            }
+        }
+
+        // The hub doesn't always send a Content-Length, so the body is limited as it's read too
+        let reader = BufReader::new(LimitedReader {
+            inner: response,
+            limit: self.max_response_size,
+            remaining: self.max_response_size,
+        });
+
+        List::from_reader(reader, subtrees)
+    }
+}
+
+/// A reader that fails once more than `limit` bytes have been read, where `Read::take` would
+/// silently truncate.
+struct LimitedReader<R> {
+    inner: R,
+    limit: u64,
+    remaining: u64,
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Allow one byte past the limit, so a response of exactly `limit` bytes still reads
+        let max = usize::try_from(self.remaining.saturating_add(1)).unwrap_or(usize::MAX);
+        let len = buf.len().min(max);
+
+        let n = self.inner.read(&mut buf[..len])?;
+        if n as u64 > self.remaining {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "router status response is more than max_response_size of {} bytes",
+                    self.limit
+                ),
+            ));
+        }
+        self.remaining -= n as u64;
+
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUTER_STATUS: &[u8] = br#"{"1.3.6.1.2.1.69.1.4.1.0": "1"}"#;
+
+    fn read(limit: u64) -> Result<List> {
+        let reader = LimitedReader {
+            inner: ROUTER_STATUS,
+            limit,
+            remaining: limit,
+        };
+        List::from_reader(BufReader::with_capacity(4, reader), &[])
+    }
+
+    #[test]
+    fn within_limit() {
+        assert!(read(ROUTER_STATUS.len() as u64).is_ok());
+    }
+
+    #[test]
+    fn over_limit() {
+        let e = read(ROUTER_STATUS.len() as u64 - 1).unwrap_err();
+        assert!(format!("{:#}", e).contains(&format!(
+            "router status response is more than max_response_size of {} bytes",
+            ROUTER_STATUS.len() - 1
+        )));
     }
 }
@@ -1,67 +1,367 @@
 use crate::client::VirginHubClient;
+use crate::settings::Settings;
+use crate::snmp::{List, OID};
 
+mod bpi_metrics;
+mod clock_metrics;
 mod configuration_metrics;
 mod downstream_metrics;
+mod ethernet_port_metrics;
+mod event_log_metrics;
+mod interface_metrics;
+mod lan_client_metrics;
+mod mta_metrics;
+mod provisioning_metrics;
 mod status_metrics;
 mod upstream_metrics;
+mod wan_metrics;
+mod wifi_metrics;
 
 use anyhow::Result;
+use bpi_metrics::BpiMetrics;
+use clock_metrics::ClockMetrics;
 use configuration_metrics::ConfigurationMetrics;
 use downstream_metrics::DownstreamMetrics;
+use ethernet_port_metrics::EthernetPortMetrics;
+pub use event_log_metrics::fetch_events;
+use event_log_metrics::EventLogMetrics;
+use interface_metrics::InterfaceMetrics;
+pub use lan_client_metrics::LanClientPrivacy;
+use lan_client_metrics::{ClientPrivacy, LanClientMetrics};
+use mta_metrics::MtaMetrics;
 use prometheus::{
-    core::{AtomicU64, Desc, GenericGauge, GenericGaugeVec},
+    core::{
+        Atomic, AtomicU64, Desc, GenericCounter, GenericCounterVec, GenericGauge, GenericGaugeVec,
+        MetricVec, MetricVecBuilder,
+    },
     proto::MetricFamily,
+    Opts,
 };
+use provisioning_metrics::ProvisioningMetrics;
 use status_metrics::StatusMetrics;
+use std::{collections::HashSet, sync::Mutex};
 use upstream_metrics::UpstreamMetrics;
+use wan_metrics::WanMetrics;
+use wifi_metrics::WiFiMetrics;
+
+/// The labels used to identify per-channel metrics.
+///
+/// The hub reassigns table indices after re-ranging, so `channel` and `all` add the channel ID
+/// and frequency so that series follow the physical channel.
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelLabels {
+    #[default]
+    Index,
+    Channel,
+    All,
+}
+
+impl ChannelLabels {
+    pub fn names(self) -> &'static [&'static str] {
+        match self {
+            ChannelLabels::Index => &["index"],
+            ChannelLabels::Channel => &["channel_id", "frequency_hz"],
+            ChannelLabels::All => &["index", "channel_id", "frequency_hz"],
+        }
+    }
+
+    pub fn values(self, index: &str, channel_id: u64, frequency: u64) -> Vec<String> {
+        match self {
+            ChannelLabels::Index => vec![index.to_owned()],
+            ChannelLabels::Channel => vec![channel_id.to_string(), frequency.to_string()],
+            ChannelLabels::All => vec![
+                index.to_owned(),
+                channel_id.to_string(),
+                frequency.to_string(),
+            ],
+        }
+    }
+}
 
 pub type UIntGauge = GenericGauge<AtomicU64>;
 pub type UIntGaugeVec = GenericGaugeVec<AtomicU64>;
+pub type UIntCounter = GenericCounter<AtomicU64>;
+pub type UIntCounterVec = GenericCounterVec<AtomicU64>;
+
+/// Sets a counter to a value read from the hub, starting again from zero if the hub's counter
+/// has been reset or has wrapped.
+pub fn set_counter(counter: &UIntCounter, value: u64) {
+    let current = counter.get();
+    if value < current {
+        counter.reset();
+        counter.inc_by(value);
+    } else {
+        counter.inc_by(value - current);
+    }
+}
+
+/// Counts a row missing from a left joined table, so the metrics it would have provided are
+/// skipped rather than failing the scrape. The table is labelled by OID, as for parse errors.
+pub fn count_missing_row<T>(
+    missing_table_rows: &UIntCounterVec,
+    table: &OID,
+    row: Option<T>,
+) -> Option<T> {
+    if row.is_none() {
+        missing_table_rows
+            .with_label_values(&[&table.to_string()])
+            .inc();
+    }
+    row
+}
+
+/// A metric with a series per table row, e.g. per channel.
+pub trait RowMetric {
+    fn reset(&self);
+}
+
+impl<T: MetricVecBuilder> RowMetric for MetricVec<T> {
+    fn reset(&self) {
+        MetricVec::reset(self);
+    }
+}
+
+/// Clears per-row metrics before they're set from a fresh table, as rows that have gone away
+/// (e.g. a channel the hub no longer uses) would otherwise keep being exported under their old
+/// labels.
+pub fn reset_rows(metrics: &[&dyn RowMetric]) {
+    for metric in metrics {
+        metric.reset();
+    }
+}
+
+/// Sets a gauge that the hub doesn't always have a value for, leaving it out of the scrape
+/// rather than exporting a misleading 0. The gauge is a vec without labels, as a plain gauge
+/// can't be removed.
+pub fn set_optional_gauge<P: Atomic>(gauge: &GenericGaugeVec<P>, value: Option<P::T>) {
+    match value {
+        Some(value) => gauge.with_label_values(&[]).set(value),
+        None => gauge.reset(),
+    }
+}
+
+/// Reads a value that a collector can do without, logging it rather than failing the scrape
+/// when it doesn't parse.
+pub fn log_parse_error<T>(oid: &OID, value: Result<Option<T>>) -> Option<T> {
+    value.unwrap_or_else(|e| {
+        log::warn!("ignoring {}: {:?}", oid, e);
+        None
+    })
+}
+
+/// Sets an OpenMetrics style state set, where `state` is 1 and every other state is 0.
+pub fn set_state_set(gauge: &UIntGaugeVec, states: &[&str], state: &str) {
+    for s in states {
+        gauge.with_label_values(&[s]).set((*s == state) as u64);
+    }
+}
+
+/// The series a metric exported, keyed by their labels, e.g. `channel_id=4,index=3`.
+#[cfg(test)]
+pub fn series(metric: &dyn prometheus::core::Collector) -> std::collections::BTreeMap<String, f64> {
+    metric
+        .collect()
+        .iter()
+        .flat_map(|family| family.get_metric())
+        .map(|metric| {
+            let labels = metric
+                .get_label()
+                .iter()
+                .map(|label| format!("{}={}", label.get_name(), label.get_value()))
+                .collect::<Vec<_>>()
+                .join(",");
+            let value = if metric.has_counter() {
+                metric.get_counter().get_value()
+            } else {
+                metric.get_gauge().get_value()
+            };
+            (labels, value)
+        })
+        .collect()
+}
 
 pub struct Collector {
     client: VirginHubClient,
+    subtrees: Vec<OID>,
 
     up: UIntGauge,
+    missing_table_rows: UIntCounterVec,
+    parse_errors: UIntCounterVec,
+    logged_row_errors: Mutex<HashSet<(OID, String)>>,
+
     status_metrics: StatusMetrics,
     downstream_metrics: DownstreamMetrics,
     upstream_metrics: UpstreamMetrics,
     configuration_metrics: ConfigurationMetrics,
+    interface_metrics: InterfaceMetrics,
+    lan_client_metrics: LanClientMetrics,
+    wifi_metrics: WiFiMetrics,
+    ethernet_port_metrics: EthernetPortMetrics,
+    wan_metrics: WanMetrics,
+    provisioning_metrics: ProvisioningMetrics,
+    clock_metrics: ClockMetrics,
+    bpi_metrics: BpiMetrics,
+    mta_metrics: Option<MtaMetrics>,
+    event_log_metrics: EventLogMetrics,
 }
 
 impl Collector {
-    pub fn new(client: VirginHubClient) -> Result<Self> {
+    pub fn new(client: VirginHubClient, settings: &Settings) -> Result<Self> {
+        let missing_table_rows = UIntCounterVec::new(
+            Opts::new(
+                "missing_table_rows_total",
+                "Rows Missing From A Joined Table",
+            ),
+            &["table"],
+        )?;
+
+        // Only what the collectors read is kept from the router status, the rest is skipped as
+        // it's decoded
+        let mut subtrees = [
+            StatusMetrics::SUBTREES,
+            DownstreamMetrics::SUBTREES,
+            UpstreamMetrics::SUBTREES,
+            ConfigurationMetrics::SUBTREES,
+            InterfaceMetrics::SUBTREES,
+            LanClientMetrics::SUBTREES,
+            WiFiMetrics::SUBTREES,
+            EthernetPortMetrics::SUBTREES,
+            WanMetrics::SUBTREES,
+            ProvisioningMetrics::SUBTREES,
+            ClockMetrics::SUBTREES,
+            BpiMetrics::SUBTREES,
+            EventLogMetrics::SUBTREES,
+        ]
+        .concat();
+        if settings.mta {
+            subtrees.extend_from_slice(MtaMetrics::SUBTREES);
+        }
+        subtrees.sort();
+        subtrees.dedup();
+
+        let client_privacy = ClientPrivacy::new(
+            settings.lan_client_privacy,
+            settings.lan_client_hash_key.as_deref(),
+        );
+
         Ok(Collector {
             client,
+            subtrees,
 
             up: UIntGauge::new("up", "Whether the Virgin Media scrape was successful.")?,
+            missing_table_rows: missing_table_rows.clone(),
+            parse_errors: UIntCounterVec::new(
+                Opts::new("parse_errors_total", "Table Rows Skipped As Unparseable"),
+                &["table", "column"],
+            )?,
+            logged_row_errors: Mutex::new(HashSet::new()),
 
             status_metrics: StatusMetrics::new()?,
-            downstream_metrics: DownstreamMetrics::new()?,
-            upstream_metrics: UpstreamMetrics::new()?,
+            downstream_metrics: DownstreamMetrics::new(
+                settings.channel_labels,
+                missing_table_rows.clone(),
+            )?,
+            upstream_metrics: UpstreamMetrics::new(settings.channel_labels, missing_table_rows)?,
             configuration_metrics: ConfigurationMetrics::new()?,
+            interface_metrics: InterfaceMetrics::new()?,
+            lan_client_metrics: LanClientMetrics::new(client_privacy.clone())?,
+            wifi_metrics: WiFiMetrics::new(client_privacy)?,
+            ethernet_port_metrics: EthernetPortMetrics::new()?,
+            wan_metrics: WanMetrics::new()?,
+            provisioning_metrics: ProvisioningMetrics::new()?,
+            clock_metrics: ClockMetrics::new()?,
+            bpi_metrics: BpiMetrics::new()?,
+            mta_metrics: if settings.mta {
+                Some(MtaMetrics::new()?)
+            } else {
+                None
+            },
+            event_log_metrics: EventLogMetrics::new()?,
         })
     }
 
     fn update(&self) -> Result<()> {
-        let router_status = self.client.get_router_status()?;
+        let router_status = self.client.get_router_status(&self.subtrees)?;
+        self.set(&router_status)
+    }
 
-        self.status_metrics.set(&router_status)?;
-        self.downstream_metrics.set(&router_status)?;
-        self.upstream_metrics.set(&router_status)?;
-        self.configuration_metrics.set(&router_status)?;
+    /// The subtrees of the router status that the collectors read.
+    pub fn subtrees(&self) -> &[OID] {
+        &self.subtrees
+    }
+
+    /// Sets every collector's metrics from a router status.
+    pub fn set(&self, router_status: &List) -> Result<()> {
+        self.status_metrics.set(router_status)?;
+        self.downstream_metrics.set(router_status)?;
+        self.upstream_metrics.set(router_status)?;
+        self.configuration_metrics.set(router_status)?;
+        self.interface_metrics.set(router_status)?;
+        self.lan_client_metrics.set(router_status)?;
+        self.wifi_metrics.set(router_status)?;
+        self.ethernet_port_metrics.set(router_status)?;
+        self.wan_metrics.set(router_status)?;
+        self.provisioning_metrics.set(router_status)?;
+        self.clock_metrics.set(router_status)?;
+        self.bpi_metrics.set(router_status)?;
+        if let Some(mta_metrics) = &self.mta_metrics {
+            mta_metrics.set(router_status)?;
+        }
+        self.event_log_metrics.set(router_status)?;
+
+        self.count_row_errors(router_status);
 
         Ok(())
     }
+
+    fn count_row_errors(&self, router_status: &List) {
+        let mut logged_row_errors = self.logged_row_errors.lock().unwrap();
+
+        for row_error in router_status.take_row_errors() {
+            let column = row_error
+                .column
+                .as_ref()
+                .map_or_else(String::new, OID::to_string);
+            self.parse_errors
+                .with_label_values(&[&row_error.table.to_string(), &column])
+                .inc();
+
+            // A bad row is usually still bad next scrape, so only log it the first time
+            if logged_row_errors.insert((row_error.table.clone(), row_error.index.clone())) {
+                log::warn!(
+                    "skipping row {} of table {}: {}",
+                    row_error.index,
+                    row_error.table,
+                    row_error.message
+                );
+            }
+        }
+    }
 }
 
 impl prometheus::core::Collector for Collector {
     fn desc(&self) -> Vec<&Desc> {
         vec![
             self.up.desc(),
+            self.missing_table_rows.desc(),
+            self.parse_errors.desc(),
             self.status_metrics.desc(),
             self.downstream_metrics.desc(),
             self.upstream_metrics.desc(),
             self.configuration_metrics.desc(),
+            self.interface_metrics.desc(),
+            self.lan_client_metrics.desc(),
+            self.wifi_metrics.desc(),
+            self.ethernet_port_metrics.desc(),
+            self.wan_metrics.desc(),
+            self.provisioning_metrics.desc(),
+            self.clock_metrics.desc(),
+            self.bpi_metrics.desc(),
+            self.mta_metrics
+                .as_ref()
+                .map_or_else(Vec::new, |mta_metrics| mta_metrics.desc()),
+            self.event_log_metrics.desc(),
         ]
         .into_iter()
         .flatten()
@@ -80,10 +380,24 @@ impl prometheus::core::Collector for Collector {
 
         vec![
             self.up.collect(),
+            self.missing_table_rows.collect(),
+            self.parse_errors.collect(),
             self.status_metrics.collect(),
             self.downstream_metrics.collect(),
             self.upstream_metrics.collect(),
             self.configuration_metrics.collect(),
+            self.interface_metrics.collect(),
+            self.lan_client_metrics.collect(),
+            self.wifi_metrics.collect(),
+            self.ethernet_port_metrics.collect(),
+            self.wan_metrics.collect(),
+            self.provisioning_metrics.collect(),
+            self.clock_metrics.collect(),
+            self.bpi_metrics.collect(),
+            self.mta_metrics
+                .as_ref()
+                .map_or_else(Vec::new, |mta_metrics| mta_metrics.collect()),
+            self.event_log_metrics.collect(),
         ]
         .into_iter()
         .flatten()
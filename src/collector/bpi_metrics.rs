@@ -0,0 +1,300 @@
+use super::{
+    set_counter, set_optional_gauge, set_state_set, UIntCounter, UIntCounterVec, UIntGaugeVec,
+};
+use crate::snmp::{oid, DateAndTime, EnumValue, Enumeration, List, Table, TableRow, OID};
+
+use anyhow::Result;
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    IntGaugeVec, Opts,
+};
+use std::{collections::HashSet, rc::Rc, sync::Mutex};
+
+const DOCS_BPI2_CM_BASE_TABLE: OID = oid!("1.3.6.1.2.1.126.1.1.1"); // docsBpi2CmBaseTable
+const DOCS_BPI2_CM_PRIVACY_ENABLE: OID = oid!("1.3.6.1.2.1.126.1.1.1.1.1"); // docsBpi2CmPrivacyEnable
+const DOCS_BPI2_CM_AUTH_STATE: OID = oid!("1.3.6.1.2.1.126.1.1.1.1.3"); // docsBpi2CmAuthState
+const DOCS_BPI2_CM_AUTH_EXPIRES_NEW: OID = oid!("1.3.6.1.2.1.126.1.1.1.1.6"); // docsBpi2CmAuthExpiresNew
+const DOCS_BPI2_CM_AUTH_GRACE_TIME: OID = oid!("1.3.6.1.2.1.126.1.1.1.1.8"); // docsBpi2CmAuthGraceTime
+const DOCS_BPI2_CM_TEK_GRACE_TIME: OID = oid!("1.3.6.1.2.1.126.1.1.1.1.9"); // docsBpi2CmTEKGraceTime
+const DOCS_BPI2_CM_AUTH_REJECTS: OID = oid!("1.3.6.1.2.1.126.1.1.1.1.20"); // docsBpi2CmAuthRejects
+const DOCS_BPI2_CM_AUTH_INVALIDS: OID = oid!("1.3.6.1.2.1.126.1.1.1.1.21"); // docsBpi2CmAuthInvalids
+
+#[derive(Clone, Copy, Debug, Enumeration)]
+#[snmp(name = "docsBpi2CmAuthState")]
+enum AuthState {
+    Start = 1,
+    AuthWait = 2,
+    Authorized = 3,
+    ReauthWait = 4,
+    AuthRejectWait = 5,
+    Silent = 6,
+}
+
+#[derive(Clone, Debug, TableRow)]
+struct BpiBase {
+    #[snmp(column = DOCS_BPI2_CM_PRIVACY_ENABLE, kind = truth_value)]
+    privacy_enable: bool,
+    #[snmp(column = DOCS_BPI2_CM_AUTH_STATE)]
+    auth_state: EnumValue<AuthState>,
+    #[snmp(column = DOCS_BPI2_CM_AUTH_EXPIRES_NEW, kind = date_and_time)]
+    auth_expires_new: Option<DateAndTime>,
+    #[snmp(column = DOCS_BPI2_CM_AUTH_GRACE_TIME)]
+    auth_grace_time: Option<u64>,
+    #[snmp(column = DOCS_BPI2_CM_TEK_GRACE_TIME)]
+    tek_grace_time: Option<u64>,
+    #[snmp(column = DOCS_BPI2_CM_AUTH_REJECTS, kind = counter32)]
+    auth_rejects: Option<u64>,
+    #[snmp(column = DOCS_BPI2_CM_AUTH_INVALIDS, kind = counter32)]
+    auth_invalids: Option<u64>,
+}
+
+const DOCS_BPI2_CM_TEK_TABLE: OID = oid!("1.3.6.1.2.1.126.1.1.2"); // docsBpi2CmTEKTable
+const DOCS_BPI2_CM_TEK_STATE: OID = oid!("1.3.6.1.2.1.126.1.1.2.1.5"); // docsBpi2CmTEKState
+const DOCS_BPI2_CM_TEK_EXPIRES_NEW: OID = oid!("1.3.6.1.2.1.126.1.1.2.1.8"); // docsBpi2CmTEKExpiresNew
+const DOCS_BPI2_CM_TEK_KEY_REJECTS: OID = oid!("1.3.6.1.2.1.126.1.1.2.1.11"); // docsBpi2CmTEKKeyRejects
+const DOCS_BPI2_CM_TEK_INVALIDS: OID = oid!("1.3.6.1.2.1.126.1.1.2.1.12"); // docsBpi2CmTEKInvalids
+
+#[derive(Clone, Copy, Debug, Enumeration)]
+#[snmp(name = "docsBpi2CmTEKState")]
+enum TekState {
+    Start = 1,
+    OpWait = 2,
+    OpReauthWait = 3,
+    Operational = 4,
+    RekeyWait = 5,
+    RekeyReauthWait = 6,
+}
+
+#[derive(Clone, Debug, TableRow)]
+struct Tek {
+    #[snmp(column = DOCS_BPI2_CM_TEK_STATE)]
+    state: EnumValue<TekState>,
+    #[snmp(column = DOCS_BPI2_CM_TEK_EXPIRES_NEW, kind = date_and_time)]
+    expires_new: Option<DateAndTime>,
+    #[snmp(column = DOCS_BPI2_CM_TEK_KEY_REJECTS, kind = counter32)]
+    key_rejects: Option<u64>,
+    #[snmp(column = DOCS_BPI2_CM_TEK_INVALIDS, kind = counter32)]
+    invalids: Option<u64>,
+}
+
+pub struct BpiMetrics {
+    bpi_enabled: UIntGaugeVec,
+    bpi_auth_status: UIntGaugeVec,
+    bpi_auth_expiry_timestamp_seconds: IntGaugeVec,
+    bpi_auth_grace_time: UIntGaugeVec,
+    bpi_tek_grace_time: UIntGaugeVec,
+    bpi_auth_rejects: UIntCounter,
+    bpi_auth_invalids: UIntCounter,
+    bpi_tek_status: UIntGaugeVec,
+    bpi_tek_expiry_timestamp_seconds: IntGaugeVec,
+    bpi_tek_key_rejects: UIntCounterVec,
+    bpi_tek_invalids: UIntCounterVec,
+
+    tek_labels: Mutex<HashSet<String>>,
+}
+
+impl BpiMetrics {
+    pub const SUBTREES: &'static [OID] = &[DOCS_BPI2_CM_BASE_TABLE, DOCS_BPI2_CM_TEK_TABLE];
+
+    pub fn new() -> Result<Self> {
+        Ok(BpiMetrics {
+            bpi_enabled: UIntGaugeVec::new(Opts::new("bpi_enabled", "BPI+ Privacy Enabled"), &[])?,
+            bpi_auth_status: UIntGaugeVec::new(
+                Opts::new("bpi_auth_status", "BPI+ Authorization State"),
+                &["state"],
+            )?,
+            bpi_auth_expiry_timestamp_seconds: IntGaugeVec::new(
+                Opts::new(
+                    "bpi_auth_expiry_timestamp_seconds",
+                    "BPI+ Authorization Key Expiry (Unix timestamp)",
+                ),
+                &[],
+            )?,
+            bpi_auth_grace_time: UIntGaugeVec::new(
+                Opts::new(
+                    "bpi_auth_grace_time_seconds",
+                    "BPI+ Authorization Key Grace Time (seconds)",
+                ),
+                &[],
+            )?,
+            bpi_tek_grace_time: UIntGaugeVec::new(
+                Opts::new(
+                    "bpi_tek_grace_time_seconds",
+                    "BPI+ Traffic Encryption Key Grace Time (seconds)",
+                ),
+                &[],
+            )?,
+            bpi_auth_rejects: UIntCounter::new(
+                "bpi_auth_rejects_total",
+                "BPI+ Authorization Rejects",
+            )?,
+            bpi_auth_invalids: UIntCounter::new(
+                "bpi_auth_invalids_total",
+                "BPI+ Authorization Invalids",
+            )?,
+            bpi_tek_status: UIntGaugeVec::new(
+                Opts::new("bpi_tek_status", "BPI+ Traffic Encryption Key State"),
+                &["said", "state"],
+            )?,
+            bpi_tek_expiry_timestamp_seconds: IntGaugeVec::new(
+                Opts::new(
+                    "bpi_tek_expiry_timestamp_seconds",
+                    "BPI+ Traffic Encryption Key Expiry (Unix timestamp)",
+                ),
+                &["said"],
+            )?,
+            bpi_tek_key_rejects: UIntCounterVec::new(
+                Opts::new(
+                    "bpi_tek_key_rejects_total",
+                    "BPI+ Traffic Encryption Key Rejects",
+                ),
+                &["said"],
+            )?,
+            bpi_tek_invalids: UIntCounterVec::new(
+                Opts::new(
+                    "bpi_tek_invalids_total",
+                    "BPI+ Traffic Encryption Key Invalids",
+                ),
+                &["said"],
+            )?,
+
+            tek_labels: Mutex::new(HashSet::new()),
+        })
+    }
+
+    pub fn set(&self, router_status: &List) -> Result<()> {
+        let bpi_base_table: Rc<Table<BpiBase, u32>> =
+            router_status.parse_table(&DOCS_BPI2_CM_BASE_TABLE)?;
+        // Indexed by ifIndex and SAID
+        let tek_table: Rc<Table<Tek, (u32, u32)>> =
+            router_status.parse_table(&DOCS_BPI2_CM_TEK_TABLE)?;
+
+        self.bpi_tek_status.reset();
+        self.bpi_tek_expiry_timestamp_seconds.reset();
+
+        // There is a single row, for the cable modem MAC interface
+        let bpi_base = bpi_base_table.iter().min_by_key(|(index, _)| *index);
+
+        let base = bpi_base.map(|(_, bpi_base)| bpi_base);
+        set_optional_gauge(
+            &self.bpi_enabled,
+            base.map(|bpi_base| bpi_base.privacy_enable as u64),
+        );
+        set_optional_gauge(
+            &self.bpi_auth_expiry_timestamp_seconds,
+            base.and_then(|bpi_base| bpi_base.auth_expires_new)
+                .and_then(|expires| expires.timestamp()),
+        );
+        set_optional_gauge(
+            &self.bpi_auth_grace_time,
+            base.and_then(|bpi_base| bpi_base.auth_grace_time),
+        );
+        set_optional_gauge(
+            &self.bpi_tek_grace_time,
+            base.and_then(|bpi_base| bpi_base.tek_grace_time),
+        );
+
+        if let Some(bpi_base) = base {
+            set_state_set(
+                &self.bpi_auth_status,
+                &[AuthState::LABELS, &["unknown"]].concat(),
+                bpi_base
+                    .auth_state
+                    .known()
+                    .map_or("unknown", AuthState::label),
+            );
+            if let Some(auth_rejects) = bpi_base.auth_rejects {
+                set_counter(&self.bpi_auth_rejects, auth_rejects);
+            }
+            if let Some(auth_invalids) = bpi_base.auth_invalids {
+                set_counter(&self.bpi_auth_invalids, auth_invalids);
+            }
+        } else {
+            self.bpi_auth_status.reset();
+        }
+
+        let mut tek_labels = HashSet::new();
+
+        let teks = bpi_base
+            .into_iter()
+            .flat_map(|(if_index, _)| tek_table.with_prefix(if_index));
+
+        for ((_, said), tek) in teks {
+            let said = said.to_string();
+
+            let tek_state = tek.state.known().map_or("unknown", TekState::label);
+            for state in TekState::LABELS.iter().chain(&["unknown"]) {
+                self.bpi_tek_status
+                    .with_label_values(&[&said, state])
+                    .set((*state == tek_state) as u64);
+            }
+            if let Some(expires) = tek.expires_new.and_then(|expires| expires.timestamp()) {
+                self.bpi_tek_expiry_timestamp_seconds
+                    .with_label_values(&[&said])
+                    .set(expires);
+            }
+            if let Some(key_rejects) = tek.key_rejects {
+                set_counter(
+                    &self.bpi_tek_key_rejects.with_label_values(&[&said]),
+                    key_rejects,
+                );
+            }
+            if let Some(invalids) = tek.invalids {
+                set_counter(&self.bpi_tek_invalids.with_label_values(&[&said]), invalids);
+            }
+
+            tek_labels.insert(said);
+        }
+
+        // Security associations come and go with dynamic service flows
+        let mut previous_tek_labels = self.tek_labels.lock().unwrap();
+        for said in previous_tek_labels.difference(&tek_labels) {
+            let _ = self.bpi_tek_key_rejects.remove_label_values(&[said]);
+            let _ = self.bpi_tek_invalids.remove_label_values(&[said]);
+        }
+        *previous_tek_labels = tek_labels;
+
+        Ok(())
+    }
+}
+
+impl Collector for BpiMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![
+            self.bpi_enabled.desc(),
+            self.bpi_auth_status.desc(),
+            self.bpi_auth_expiry_timestamp_seconds.desc(),
+            self.bpi_auth_grace_time.desc(),
+            self.bpi_tek_grace_time.desc(),
+            self.bpi_auth_rejects.desc(),
+            self.bpi_auth_invalids.desc(),
+            self.bpi_tek_status.desc(),
+            self.bpi_tek_expiry_timestamp_seconds.desc(),
+            self.bpi_tek_key_rejects.desc(),
+            self.bpi_tek_invalids.desc(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        vec![
+            self.bpi_enabled.collect(),
+            self.bpi_auth_status.collect(),
+            self.bpi_auth_expiry_timestamp_seconds.collect(),
+            self.bpi_auth_grace_time.collect(),
+            self.bpi_tek_grace_time.collect(),
+            self.bpi_auth_rejects.collect(),
+            self.bpi_auth_invalids.collect(),
+            self.bpi_tek_status.collect(),
+            self.bpi_tek_expiry_timestamp_seconds.collect(),
+            self.bpi_tek_key_rejects.collect(),
+            self.bpi_tek_invalids.collect(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
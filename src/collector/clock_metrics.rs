@@ -0,0 +1,210 @@
+use super::{log_parse_error, set_optional_gauge, UIntGaugeVec};
+use crate::snmp::{oid, DateAndTime, EnumValue, Enumeration, List, OID};
+
+use anyhow::{Context, Result};
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    IntGaugeVec, Opts,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DOCS_DEV_DATE_TIME: OID = oid!("1.3.6.1.2.1.69.1.1.2"); // docsDevDateTime
+const DOCS_DEV_SERVER_BOOT_STATE: OID = oid!("1.3.6.1.2.1.69.1.4.1"); // docsDevServerBootState
+
+/// 2000-01-01. Without a time of day the hub's clock counts up from 1970, so it won't reach this
+/// for decades.
+const TOD_FLOOR: i64 = 946_684_800;
+
+#[derive(Clone, Copy, Debug, Enumeration)]
+#[snmp(name = "docsDevServerBootState")]
+enum ServerBootState {
+    Operational = 1,
+    Disabled = 2,
+    WaitingForDhcpOffer = 3,
+    WaitingForDhcpResponse = 4,
+    WaitingForTimeServer = 5,
+    WaitingForTftp = 6,
+    RefusedByCmts = 7,
+    ForwardingDenied = 8,
+    Other = 9,
+    Unknown = 10,
+}
+
+impl ServerBootState {
+    /// Whether provisioning hasn't yet asked the time server, which it does after DHCP.
+    fn before_time_server(self) -> bool {
+        use ServerBootState::*;
+
+        matches!(
+            self,
+            WaitingForDhcpOffer | WaitingForDhcpResponse | WaitingForTimeServer
+        )
+    }
+}
+
+/// Whether the hub has got a time of day. DOCSIS 3.0 hubs go on to register when the time server
+/// doesn't answer, so a later boot state doesn't mean they have one and the clock has to say.
+fn tod_synchronized(modem_time: Option<i64>, boot_state: Option<ServerBootState>) -> Option<bool> {
+    if boot_state.is_some_and(ServerBootState::before_time_server) {
+        return Some(false);
+    }
+    modem_time.map(|modem_time| modem_time >= TOD_FLOOR)
+}
+
+pub struct ClockMetrics {
+    modem_clock_timestamp_seconds: IntGaugeVec,
+    modem_clock_drift_seconds: IntGaugeVec,
+    tod_synchronized: UIntGaugeVec,
+}
+
+impl ClockMetrics {
+    pub const SUBTREES: &'static [OID] = &[DOCS_DEV_DATE_TIME, DOCS_DEV_SERVER_BOOT_STATE];
+
+    pub fn new() -> Result<Self> {
+        Ok(ClockMetrics {
+            modem_clock_timestamp_seconds: IntGaugeVec::new(
+                Opts::new(
+                    "modem_clock_timestamp_seconds",
+                    "Modem Clock (Unix timestamp)",
+                ),
+                &[],
+            )?,
+            modem_clock_drift_seconds: IntGaugeVec::new(
+                Opts::new(
+                    "modem_clock_drift_seconds",
+                    "Modem Clock minus Exporter Clock (seconds)",
+                ),
+                &[],
+            )?,
+            tod_synchronized: UIntGaugeVec::new(
+                Opts::new("tod_synchronized", "Time of Day Synchronized"),
+                &[],
+            )?,
+        })
+    }
+
+    pub fn set(&self, router_status: &List) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("system clock is before the Unix epoch")?
+            .as_secs() as i64;
+
+        let modem_time = log_parse_error(
+            &DOCS_DEV_DATE_TIME,
+            router_status.parse_optional_scalar::<DateAndTime>(&DOCS_DEV_DATE_TIME),
+        )
+        .and_then(|date_time| date_time.timestamp());
+        let boot_state = log_parse_error(
+            &DOCS_DEV_SERVER_BOOT_STATE,
+            router_status
+                .parse_optional_scalar::<EnumValue<ServerBootState>>(&DOCS_DEV_SERVER_BOOT_STATE),
+        )
+        .and_then(EnumValue::known);
+        let tod_synchronized = tod_synchronized(modem_time, boot_state);
+
+        set_optional_gauge(&self.modem_clock_timestamp_seconds, modem_time);
+        set_optional_gauge(&self.tod_synchronized, tod_synchronized.map(u64::from));
+
+        // Until the hub has a time of day its clock counts up from 1970, so drift is meaningless
+        let drift = modem_time.filter(|_| tod_synchronized == Some(true));
+        set_optional_gauge(
+            &self.modem_clock_drift_seconds,
+            drift.map(|modem_time| modem_time - now),
+        );
+
+        Ok(())
+    }
+}
+
+impl Collector for ClockMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![
+            self.modem_clock_timestamp_seconds.desc(),
+            self.modem_clock_drift_seconds.desc(),
+            self.tod_synchronized.desc(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        vec![
+            self.modem_clock_timestamp_seconds.collect(),
+            self.modem_clock_drift_seconds.collect(),
+            self.tod_synchronized.collect(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::series;
+
+    fn set(json: &str) -> ClockMetrics {
+        let router_status = List::from_reader(json.as_bytes(), ClockMetrics::SUBTREES).unwrap();
+        let clock_metrics = ClockMetrics::new().unwrap();
+        clock_metrics.set(&router_status).unwrap();
+        clock_metrics
+    }
+
+    fn value(metric: &dyn Collector) -> Option<f64> {
+        series(metric).get("").copied()
+    }
+
+    #[test]
+    fn synchronized() {
+        let clock_metrics = set(r#"{
+            "1.3.6.1.2.1.69.1.1.2.0": "$07ea0a12150208002b0000",
+            "1.3.6.1.2.1.69.1.4.1.0": "1"
+        }"#);
+
+        assert_eq!(value(&clock_metrics.tod_synchronized), Some(1.0));
+        assert_eq!(
+            value(&clock_metrics.modem_clock_timestamp_seconds),
+            Some(1_792_357_328.0)
+        );
+        assert!(value(&clock_metrics.modem_clock_drift_seconds).is_some());
+    }
+
+    #[test]
+    fn operational_without_time_of_day() {
+        // Registered, but the clock is still counting up from 1970
+        let clock_metrics = set(r#"{
+            "1.3.6.1.2.1.69.1.1.2.0": "$07b2010100050000",
+            "1.3.6.1.2.1.69.1.4.1.0": "1"
+        }"#);
+
+        assert_eq!(value(&clock_metrics.tod_synchronized), Some(0.0));
+        assert_eq!(
+            value(&clock_metrics.modem_clock_timestamp_seconds),
+            Some(300.0)
+        );
+        assert_eq!(value(&clock_metrics.modem_clock_drift_seconds), None);
+    }
+
+    #[test]
+    fn waiting_for_time_server() {
+        let clock_metrics = set(r#"{"1.3.6.1.2.1.69.1.4.1.0": "5"}"#);
+
+        assert_eq!(value(&clock_metrics.tod_synchronized), Some(0.0));
+        assert_eq!(value(&clock_metrics.modem_clock_timestamp_seconds), None);
+    }
+
+    #[test]
+    fn unparseable_values_are_skipped() {
+        let clock_metrics = set(r#"{
+            "1.3.6.1.2.1.69.1.1.2.0": "$07ea0a12",
+            "1.3.6.1.2.1.69.1.4.1.0": "operational"
+        }"#);
+
+        assert_eq!(value(&clock_metrics.tod_synchronized), None);
+        assert_eq!(value(&clock_metrics.modem_clock_timestamp_seconds), None);
+        assert_eq!(value(&clock_metrics.modem_clock_drift_seconds), None);
+    }
+}
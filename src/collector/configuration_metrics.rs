@@ -1,64 +1,77 @@
-use super::UIntGauge;
-use crate::snmp::{List, Table, TableEntry, OID};
+use super::{reset_rows, set_counter, set_optional_gauge, UIntCounterVec, UIntGauge, UIntGaugeVec};
+use crate::snmp::{oid, Bits, EnumValue, Enumeration, List, Table, TableRow, OID};
 
-use anyhow::{bail, Context, Result};
+use anyhow::Result;
 use prometheus::{
     core::{Collector, Desc},
     proto::MetricFamily,
+    IntGaugeVec, Opts,
 };
-use std::{convert::TryFrom, str::FromStr};
-
-const DOCSIS_BASE_CAPABILITY: OID = OID::new("1.3.6.1.2.1.10.127.1.1.5"); // DocsisBaseCapability
+use std::{collections::HashSet, rc::Rc, sync::Mutex};
+
+const DOCSIS_BASE_CAPABILITY: OID = oid!("1.3.6.1.2.1.10.127.1.1.5"); // DocsisBaseCapability
+
+const DOCS_IF31_CM_SYSTEM_CFG_STATE_TABLE: OID = oid!("1.3.6.1.4.1.4491.2.1.28.1.23"); // docsIf31CmSystemCfgStateTable
+const DOCS_IF31_CM_SYSTEM_CFG_STATE_DIPLEXER_CAPABILITY: OID =
+    oid!("1.3.6.1.4.1.4491.2.1.28.1.23.1.1"); // docsIf31CmSystemCfgStateDiplexerCapability
+const DOCS_IF31_CM_SYSTEM_CFG_STATE_DIPLEXER_CFG: OID = oid!("1.3.6.1.4.1.4491.2.1.28.1.23.1.2"); // docsIf31CmSystemCfgStateDiplexerCfg
+
+/// The DiplexerCapability bits, named by upstream band, with the band's upper edge (Hz).
+const DIPLEXER_BANDS: [(&str, u64); 5] = [
+    ("5-42mhz", 42_000_000),
+    ("5-65mhz", 65_000_000),
+    ("5-85mhz", 85_000_000),
+    ("5-117mhz", 117_000_000),
+    ("5-204mhz", 204_000_000),
+];
+
+#[derive(Clone, Debug, TableRow)]
+struct SystemCfgState {
+    #[snmp(column = DOCS_IF31_CM_SYSTEM_CFG_STATE_DIPLEXER_CAPABILITY)]
+    diplexer_capability: Option<Bits>,
+    #[snmp(column = DOCS_IF31_CM_SYSTEM_CFG_STATE_DIPLEXER_CFG)]
+    diplexer_cfg: Option<Bits>,
+}
 
-const DOCS_QOS_SERVICE_FLOW_TABLE: OID = OID::new("1.3.6.1.4.1.4491.2.1.21.1.3"); // docsQosServiceFlowTable
-const DOCS_QOS_SERVICE_FLOW_DIRECTION: OID = OID::new("1.3.6.1.4.1.4491.2.1.21.1.3.1.7"); // docsQosServiceFlowDirection
-const DOCS_QOS_SERVICE_FLOW_PRIMARY: OID = OID::new("1.3.6.1.4.1.4491.2.1.21.1.3.1.8"); // docsQosServiceFlowPrimary
+const DOCS_QOS_SERVICE_FLOW_TABLE: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.3"); // docsQosServiceFlowTable
+const DOCS_QOS_SERVICE_FLOW_DIRECTION: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.3.1.7"); // docsQosServiceFlowDirection
+const DOCS_QOS_SERVICE_FLOW_PRIMARY: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.3.1.8"); // docsQosServiceFlowPrimary
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Enumeration)]
+#[snmp(name = "docsQosServiceFlowDirection")]
 enum QOSServiceFlowDirection {
     Downstream = 1,
     Upstream = 2,
 }
 
-impl FromStr for QOSServiceFlowDirection {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use QOSServiceFlowDirection::*;
-
-        Ok(match s {
-            "1" => Downstream,
-            "2" => Upstream,
-            _ => bail!("unknown docsQosServiceFlowDirection: {}", s),
-        })
-    }
-}
-
-#[derive(Debug)]
+#[derive(Clone, Debug, TableRow)]
 struct QOSServiceFlow {
-    direction: QOSServiceFlowDirection,
+    #[snmp(column = DOCS_QOS_SERVICE_FLOW_DIRECTION)]
+    direction: EnumValue<QOSServiceFlowDirection>,
+    #[snmp(column = DOCS_QOS_SERVICE_FLOW_PRIMARY, kind = truth_value)]
     primary: bool,
 }
 
-impl TryFrom<TableEntry> for QOSServiceFlow {
-    type Error = anyhow::Error;
-
-    fn try_from(entry: TableEntry) -> Result<Self, Self::Error> {
-        Ok(QOSServiceFlow {
-            direction: entry.parse_column(&DOCS_QOS_SERVICE_FLOW_DIRECTION)?,
-            primary: entry.get_column(&DOCS_QOS_SERVICE_FLOW_PRIMARY)? == "1",
-        })
-    }
-}
-
-const DOCS_QOS_PARAM_SET_TABLE: OID = OID::new("1.3.6.1.4.1.4491.2.1.21.1.2"); // docsQosParamSetTable
-const DOCS_QOS_PARAM_SET_MAX_TRAFFIC_RATE: OID = OID::new("1.3.6.1.4.1.4491.2.1.21.1.2.1.6"); // docsQosParamSetMaxTrafficRate
-const DOCS_QOS_PARAM_SET_MAX_TRAFFIC_BURST: OID = OID::new("1.3.6.1.4.1.4491.2.1.21.1.2.1.7"); // docsQosParamSetMaxTrafficBurst
-const DOCS_QOS_PARAM_SET_MIN_RESERVED_RATE: OID = OID::new("1.3.6.1.4.1.4491.2.1.21.1.2.1.8"); // docsQosParamSetMinReservedRate
-const DOCS_QOS_PARAM_SET_MAX_CONCAT_BURST: OID = OID::new("1.3.6.1.4.1.4491.2.1.21.1.2.1.12"); // docsQosParamSetMaxConcatBurst
-const DOCS_QOS_PARAM_SET_SCHEDULING_TYPE: OID = OID::new("1.3.6.1.4.1.4491.2.1.21.1.2.1.13"); // docsQosParamSetSchedulingType
-
-#[derive(Clone, Copy, Debug)]
+const DOCS_QOS_PARAM_SET_TABLE: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2"); // docsQosParamSetTable
+const DOCS_QOS_PARAM_SET_PRIORITY: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.5"); // docsQosParamSetPriority
+const DOCS_QOS_PARAM_SET_MAX_TRAFFIC_RATE: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.6"); // docsQosParamSetMaxTrafficRate
+const DOCS_QOS_PARAM_SET_MAX_TRAFFIC_BURST: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.7"); // docsQosParamSetMaxTrafficBurst
+const DOCS_QOS_PARAM_SET_MIN_RESERVED_RATE: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.8"); // docsQosParamSetMinReservedRate
+const DOCS_QOS_PARAM_SET_MIN_RESERVED_PKT: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.9"); // docsQosParamSetMinReservedPkt
+const DOCS_QOS_PARAM_SET_ACTIVE_TIMEOUT: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.10"); // docsQosParamSetActiveTimeout
+const DOCS_QOS_PARAM_SET_ADMITTED_TIMEOUT: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.11"); // docsQosParamSetAdmittedTimeout
+const DOCS_QOS_PARAM_SET_MAX_CONCAT_BURST: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.12"); // docsQosParamSetMaxConcatBurst
+const DOCS_QOS_PARAM_SET_SCHEDULING_TYPE: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.13"); // docsQosParamSetSchedulingType
+const DOCS_QOS_PARAM_SET_NOM_POLL_INTERVAL: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.14"); // docsQosParamSetNomPollInterval
+const DOCS_QOS_PARAM_SET_TOL_POLL_JITTER: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.15"); // docsQosParamSetTolPollJitter
+const DOCS_QOS_PARAM_SET_UNSOLICIT_GRANT_SIZE: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.16"); // docsQosParamSetUnsolicitGrantSize
+const DOCS_QOS_PARAM_SET_NOM_GRANT_INTERVAL: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.17"); // docsQosParamSetNomGrantInterval
+const DOCS_QOS_PARAM_SET_TOL_GRANT_JITTER: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.18"); // docsQosParamSetTolGrantJitter
+const DOCS_QOS_PARAM_SET_GRANTS_PER_INTERVAL: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.19"); // docsQosParamSetGrantsPerInterval
+const DOCS_QOS_PARAM_SET_MAX_LATENCY: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.2.1.22"); // docsQosParamSetMaxLatency
+
+#[derive(Clone, Copy, Debug, Enumeration)]
+#[snmp(name = "docsQosParamSetSchedulingType")]
 enum QOSSchedulingType {
     Undefined = 1,
     BestEffort = 2,
@@ -68,109 +81,312 @@ enum QOSSchedulingType {
     UnsolicitedGrantService = 6,
 }
 
-impl FromStr for QOSSchedulingType {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use QOSSchedulingType::*;
-
-        Ok(match s {
-            "1" => Undefined,
-            "2" => BestEffort,
-            "3" => NonRealTimePollingService,
-            "4" => RealTimePollingService,
-            "5" => UnsolicitedGrantServiceWithAD,
-            "6" => UnsolicitedGrantService,
-            _ => bail!("unknown docsQosParamSetSchedulingType: {}", s),
-        })
-    }
-}
-
-#[derive(Debug)]
+#[derive(Clone, Debug, TableRow)]
 struct QOSParamSet {
+    #[snmp(column = DOCS_QOS_PARAM_SET_PRIORITY)]
+    priority: Option<u64>,
+    #[snmp(column = DOCS_QOS_PARAM_SET_MAX_TRAFFIC_RATE, kind = gauge32)]
     max_traffic_rate: u64,
+    #[snmp(column = DOCS_QOS_PARAM_SET_MAX_TRAFFIC_BURST, kind = gauge32)]
     max_traffic_burst: u64,
+    #[snmp(column = DOCS_QOS_PARAM_SET_MIN_RESERVED_RATE, kind = gauge32)]
     min_reserved_rate: u64,
+    #[snmp(column = DOCS_QOS_PARAM_SET_MIN_RESERVED_PKT)]
+    min_reserved_pkt: Option<u64>,
+    #[snmp(column = DOCS_QOS_PARAM_SET_ACTIVE_TIMEOUT)]
+    active_timeout: Option<u64>,
+    #[snmp(column = DOCS_QOS_PARAM_SET_ADMITTED_TIMEOUT)]
+    admitted_timeout: Option<u64>,
+    #[snmp(column = DOCS_QOS_PARAM_SET_MAX_CONCAT_BURST)]
     max_concat_burst: u64,
-    scheduling_type: QOSSchedulingType,
+    #[snmp(column = DOCS_QOS_PARAM_SET_SCHEDULING_TYPE)]
+    scheduling_type: EnumValue<QOSSchedulingType>,
+    #[snmp(column = DOCS_QOS_PARAM_SET_NOM_POLL_INTERVAL)]
+    nom_poll_interval: Option<u64>,
+    #[snmp(column = DOCS_QOS_PARAM_SET_TOL_POLL_JITTER)]
+    tol_poll_jitter: Option<u64>,
+    #[snmp(column = DOCS_QOS_PARAM_SET_UNSOLICIT_GRANT_SIZE)]
+    unsolicit_grant_size: Option<u64>,
+    #[snmp(column = DOCS_QOS_PARAM_SET_NOM_GRANT_INTERVAL)]
+    nom_grant_interval: Option<u64>,
+    #[snmp(column = DOCS_QOS_PARAM_SET_TOL_GRANT_JITTER)]
+    tol_grant_jitter: Option<u64>,
+    #[snmp(column = DOCS_QOS_PARAM_SET_GRANTS_PER_INTERVAL)]
+    grants_per_interval: Option<u64>,
+    #[snmp(column = DOCS_QOS_PARAM_SET_MAX_LATENCY)]
+    max_latency: Option<u64>,
 }
 
-impl TryFrom<TableEntry> for QOSParamSet {
-    type Error = anyhow::Error;
+const DOCS_QOS_SERVICE_FLOW_STATS_TABLE: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.4"); // docsQosServiceFlowStatsTable
+const DOCS_QOS_SERVICE_FLOW_PKTS: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.4.1.1"); // docsQosServiceFlowPkts
+const DOCS_QOS_SERVICE_FLOW_OCTETS: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.4.1.2"); // docsQosServiceFlowOctets
+const DOCS_QOS_SERVICE_FLOW_POLICED_DROP_PKTS: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.4.1.6"); // docsQosServiceFlowPolicedDropPkts
+const DOCS_QOS_SERVICE_FLOW_POLICED_DELAY_PKTS: OID = oid!("1.3.6.1.4.1.4491.2.1.21.1.4.1.7"); // docsQosServiceFlowPolicedDelayPkts
+
+#[derive(Clone, Debug, TableRow)]
+struct QOSServiceFlowStats {
+    #[snmp(column = DOCS_QOS_SERVICE_FLOW_PKTS, kind = counter64)]
+    pkts: u64,
+    #[snmp(column = DOCS_QOS_SERVICE_FLOW_OCTETS, kind = counter64)]
+    octets: u64,
+    #[snmp(column = DOCS_QOS_SERVICE_FLOW_POLICED_DROP_PKTS, kind = counter32)]
+    policed_drop_pkts: u64,
+    #[snmp(column = DOCS_QOS_SERVICE_FLOW_POLICED_DELAY_PKTS, kind = counter32)]
+    policed_delay_pkts: u64,
+}
 
-    fn try_from(entry: TableEntry) -> Result<Self, Self::Error> {
-        Ok(QOSParamSet {
-            max_traffic_rate: entry.parse_column(&DOCS_QOS_PARAM_SET_MAX_TRAFFIC_RATE)?,
-            max_traffic_burst: entry.parse_column(&DOCS_QOS_PARAM_SET_MAX_TRAFFIC_BURST)?,
-            min_reserved_rate: entry.parse_column(&DOCS_QOS_PARAM_SET_MIN_RESERVED_RATE)?,
-            max_concat_burst: entry.parse_column(&DOCS_QOS_PARAM_SET_MAX_CONCAT_BURST)?,
-            scheduling_type: entry.parse_column(&DOCS_QOS_PARAM_SET_SCHEDULING_TYPE)?,
-        })
-    }
+const SERVICE_FLOW_LABELS: [&str; 4] = ["sfid", "direction", "primary", "scheduling_type"];
+
+fn service_flow_gauge(name: &str, help: &str) -> Result<UIntGaugeVec> {
+    Ok(UIntGaugeVec::new(
+        Opts::new(name, help),
+        &SERVICE_FLOW_LABELS,
+    )?)
 }
 
 pub struct ConfigurationMetrics {
     docsis_mode: UIntGauge,
-
-    primary_downstream_sfid: UIntGauge,
-    primary_downstream_max_traffic_rate: UIntGauge,
-    primary_downstream_max_traffic_burst: UIntGauge,
-    primary_downstream_min_reserved_rate: UIntGauge,
-
-    primary_upstream_sfid: UIntGauge,
-    primary_upstream_max_traffic_rate: UIntGauge,
-    primary_upstream_max_traffic_burst: UIntGauge,
-    primary_upstream_min_reserved_rate: UIntGauge,
-    primary_upstream_max_concat_burst: UIntGauge,
-    primary_upstream_scheduling_type: UIntGauge,
+    diplexer_capability: UIntGaugeVec,
+    diplexer_config: UIntGaugeVec,
+    diplexer_upstream_upper_edge: UIntGaugeVec,
+
+    primary_downstream_sfid: UIntGaugeVec,
+    primary_downstream_max_traffic_rate: UIntGaugeVec,
+    primary_downstream_max_traffic_burst: UIntGaugeVec,
+    primary_downstream_min_reserved_rate: UIntGaugeVec,
+
+    primary_upstream_sfid: UIntGaugeVec,
+    primary_upstream_max_traffic_rate: UIntGaugeVec,
+    primary_upstream_max_traffic_burst: UIntGaugeVec,
+    primary_upstream_min_reserved_rate: UIntGaugeVec,
+    primary_upstream_max_concat_burst: UIntGaugeVec,
+    primary_upstream_scheduling_type: IntGaugeVec,
+    primary_upstream_service_flow_info: UIntGaugeVec,
+
+    service_flow_info: UIntGaugeVec,
+    service_flow_priority: UIntGaugeVec,
+    service_flow_max_traffic_rate: UIntGaugeVec,
+    service_flow_max_traffic_burst: UIntGaugeVec,
+    service_flow_min_reserved_rate: UIntGaugeVec,
+    service_flow_min_reserved_pkt: UIntGaugeVec,
+    service_flow_active_timeout: UIntGaugeVec,
+    service_flow_admitted_timeout: UIntGaugeVec,
+    service_flow_max_concat_burst: UIntGaugeVec,
+    service_flow_scheduling_type: IntGaugeVec,
+    service_flow_nom_poll_interval: UIntGaugeVec,
+    service_flow_tol_poll_jitter: UIntGaugeVec,
+    service_flow_unsolicit_grant_size: UIntGaugeVec,
+    service_flow_nom_grant_interval: UIntGaugeVec,
+    service_flow_tol_grant_jitter: UIntGaugeVec,
+    service_flow_grants_per_interval: UIntGaugeVec,
+    service_flow_max_latency: UIntGaugeVec,
+
+    service_flow_packets: UIntCounterVec,
+    service_flow_bytes: UIntCounterVec,
+    service_flow_policed_drop_packets: UIntCounterVec,
+    service_flow_policed_delay_packets: UIntCounterVec,
+    service_flow_stats_labels: Mutex<HashSet<[String; 2]>>,
 }
 
 impl ConfigurationMetrics {
+    pub const SUBTREES: &'static [OID] = &[
+        DOCSIS_BASE_CAPABILITY,
+        DOCS_IF31_CM_SYSTEM_CFG_STATE_TABLE,
+        DOCS_QOS_SERVICE_FLOW_TABLE,
+        DOCS_QOS_PARAM_SET_TABLE,
+        DOCS_QOS_SERVICE_FLOW_STATS_TABLE,
+    ];
+
     pub fn new() -> Result<Self> {
         Ok(ConfigurationMetrics {
             docsis_mode: UIntGauge::new("docsis_mode", "DOCSIS Mode")?,
+            diplexer_capability: UIntGaugeVec::new(
+                Opts::new("diplexer_capability", "Supported Diplexer Bands"),
+                &["band"],
+            )?,
+            diplexer_config: UIntGaugeVec::new(
+                Opts::new("diplexer_config", "Configured Diplexer Band"),
+                &["band"],
+            )?,
+            diplexer_upstream_upper_edge: UIntGaugeVec::new(
+                Opts::new(
+                    "diplexer_upstream_upper_edge",
+                    "Configured Diplexer Upstream Upper Band Edge (Hz)",
+                ),
+                &[],
+            )?,
+
+            primary_downstream_sfid: UIntGaugeVec::new(
+                Opts::new(
+                    "primary_downstream_sfid",
+                    "Primary Downstream Service Flow SFID",
+                ),
+                &[],
+            )?,
+            primary_downstream_max_traffic_rate: UIntGaugeVec::new(
+                Opts::new(
+                    "primary_downstream_max_traffic_rate",
+                    "Primary Downstream Service Flow Max Traffic Rate",
+                ),
+                &[],
+            )?,
+            primary_downstream_max_traffic_burst: UIntGaugeVec::new(
+                Opts::new(
+                    "primary_downstream_max_traffic_burst",
+                    "Primary Downstream Service Flow Max Traffic Burst",
+                ),
+                &[],
+            )?,
+            primary_downstream_min_reserved_rate: UIntGaugeVec::new(
+                Opts::new(
+                    "primary_downstream_min_reserved_rate",
+                    "Primary Downstream Service Flow Min Traffic Rate",
+                ),
+                &[],
+            )?,
 
-            primary_downstream_sfid: UIntGauge::new(
-                "primary_downstream_sfid",
-                "Primary Downstream Service Flow SFID",
+            primary_upstream_sfid: UIntGaugeVec::new(
+                Opts::new(
+                    "primary_upstream_sfid",
+                    "Primary Upstream Service Flow SFID",
+                ),
+                &[],
+            )?,
+            primary_upstream_max_traffic_rate: UIntGaugeVec::new(
+                Opts::new(
+                    "primary_upstream_max_traffic_rate",
+                    "Primary Upstream Service Flow Max Traffic Rate",
+                ),
+                &[],
+            )?,
+            primary_upstream_max_traffic_burst: UIntGaugeVec::new(
+                Opts::new(
+                    "primary_upstream_max_traffic_burst",
+                    "Primary Upstream Service Flow Max Traffic Burst",
+                ),
+                &[],
             )?,
-            primary_downstream_max_traffic_rate: UIntGauge::new(
-                "primary_downstream_max_traffic_rate",
-                "Primary Downstream Service Flow Max Traffic Rate",
+            primary_upstream_min_reserved_rate: UIntGaugeVec::new(
+                Opts::new(
+                    "primary_upstream_min_reserved_rate",
+                    "Primary Upstream Service Flow Min Traffic Rate",
+                ),
+                &[],
             )?,
-            primary_downstream_max_traffic_burst: UIntGauge::new(
-                "primary_downstream_max_traffic_burst",
-                "Primary Downstream Service Flow Max Traffic Burst",
+            primary_upstream_max_concat_burst: UIntGaugeVec::new(
+                Opts::new(
+                    "primary_upstream_max_concat_burst",
+                    "Primary Upstream Service Flow Max Concatenated Burst",
+                ),
+                &[],
             )?,
-            primary_downstream_min_reserved_rate: UIntGauge::new(
-                "primary_downstream_min_reserved_rate",
-                "Primary Downstream Service Flow Min Traffic Rate",
+            primary_upstream_scheduling_type: IntGaugeVec::new(
+                Opts::new(
+                    "primary_upstream_scheduling_type",
+                    "Primary Upstream Service Flow Scheduling Type",
+                ),
+                &[],
+            )?,
+            primary_upstream_service_flow_info: UIntGaugeVec::new(
+                Opts::new(
+                    "primary_upstream_service_flow_info",
+                    "Primary Upstream Service Flow Info",
+                ),
+                &["sfid", "scheduling_type"],
             )?,
 
-            primary_upstream_sfid: UIntGauge::new(
-                "primary_upstream_sfid",
-                "Primary Upstream Service Flow SFID",
+            service_flow_info: service_flow_gauge("service_flow_info", "Service Flow Info")?,
+            service_flow_priority: service_flow_gauge(
+                "service_flow_priority",
+                "Service Flow Traffic Priority",
+            )?,
+            service_flow_max_traffic_rate: service_flow_gauge(
+                "service_flow_max_traffic_rate",
+                "Service Flow Max Traffic Rate (bps)",
+            )?,
+            service_flow_max_traffic_burst: service_flow_gauge(
+                "service_flow_max_traffic_burst",
+                "Service Flow Max Traffic Burst (bytes)",
+            )?,
+            service_flow_min_reserved_rate: service_flow_gauge(
+                "service_flow_min_reserved_rate",
+                "Service Flow Min Reserved Rate (bps)",
+            )?,
+            service_flow_min_reserved_pkt: service_flow_gauge(
+                "service_flow_min_reserved_pkt",
+                "Service Flow Min Reserved Packet Size (bytes)",
+            )?,
+            service_flow_active_timeout: service_flow_gauge(
+                "service_flow_active_timeout",
+                "Service Flow Active Timeout (s)",
+            )?,
+            service_flow_admitted_timeout: service_flow_gauge(
+                "service_flow_admitted_timeout",
+                "Service Flow Admitted Timeout (s)",
+            )?,
+            service_flow_max_concat_burst: service_flow_gauge(
+                "service_flow_max_concat_burst",
+                "Service Flow Max Concatenated Burst (bytes)",
             )?,
-            primary_upstream_max_traffic_rate: UIntGauge::new(
-                "primary_upstream_max_traffic_rate",
-                "Primary Upstream Service Flow Max Traffic Rate",
+            service_flow_scheduling_type: IntGaugeVec::new(
+                Opts::new(
+                    "service_flow_scheduling_type",
+                    "Service Flow Scheduling Type",
+                ),
+                &SERVICE_FLOW_LABELS,
             )?,
-            primary_upstream_max_traffic_burst: UIntGauge::new(
-                "primary_upstream_max_traffic_burst",
-                "Primary Upstream Service Flow Max Traffic Burst",
+            service_flow_nom_poll_interval: service_flow_gauge(
+                "service_flow_nom_poll_interval",
+                "Service Flow Nominal Polling Interval (us)",
             )?,
-            primary_upstream_min_reserved_rate: UIntGauge::new(
-                "primary_upstream_min_reserved_rate",
-                "Primary Upstream Service Flow Min Traffic Rate",
+            service_flow_tol_poll_jitter: service_flow_gauge(
+                "service_flow_tol_poll_jitter",
+                "Service Flow Tolerated Poll Jitter (us)",
             )?,
-            primary_upstream_max_concat_burst: UIntGauge::new(
-                "primary_upstream_max_concat_burst",
-                "Primary Upstream Service Flow Max Concatenated Burst",
+            service_flow_unsolicit_grant_size: service_flow_gauge(
+                "service_flow_unsolicit_grant_size",
+                "Service Flow Unsolicited Grant Size (bytes)",
             )?,
-            primary_upstream_scheduling_type: UIntGauge::new(
-                "primary_upstream_scheduling_type",
-                "Primary Upstream Service Flow Scheduling Type",
+            service_flow_nom_grant_interval: service_flow_gauge(
+                "service_flow_nom_grant_interval",
+                "Service Flow Nominal Grant Interval (us)",
             )?,
+            service_flow_tol_grant_jitter: service_flow_gauge(
+                "service_flow_tol_grant_jitter",
+                "Service Flow Tolerated Grant Jitter (us)",
+            )?,
+            service_flow_grants_per_interval: service_flow_gauge(
+                "service_flow_grants_per_interval",
+                "Service Flow Grants Per Interval",
+            )?,
+            service_flow_max_latency: service_flow_gauge(
+                "service_flow_max_latency",
+                "Service Flow Max Downstream Latency (us)",
+            )?,
+
+            service_flow_packets: UIntCounterVec::new(
+                Opts::new("service_flow_packets_total", "Service Flow Packets"),
+                &["sfid", "direction"],
+            )?,
+            service_flow_bytes: UIntCounterVec::new(
+                Opts::new("service_flow_bytes_total", "Service Flow Bytes"),
+                &["sfid", "direction"],
+            )?,
+            service_flow_policed_drop_packets: UIntCounterVec::new(
+                Opts::new(
+                    "service_flow_policed_drop_packets_total",
+                    "Service Flow Packets Dropped by Rate Limiting",
+                ),
+                &["sfid", "direction"],
+            )?,
+            service_flow_policed_delay_packets: UIntCounterVec::new(
+                Opts::new(
+                    "service_flow_policed_delay_packets_total",
+                    "Service Flow Packets Delayed by Rate Limiting",
+                ),
+                &["sfid", "direction"],
+            )?,
+            service_flow_stats_labels: Mutex::new(HashSet::new()),
         })
     }
 
@@ -178,73 +394,291 @@ impl ConfigurationMetrics {
         let docsis_mode = router_status.parse_scalar(&DOCSIS_BASE_CAPABILITY)?;
         self.docsis_mode.set(docsis_mode);
 
-        let qos_service_flow_table: Table<QOSServiceFlow> =
+        // Only DOCSIS 3.1 firmware has the system configuration table
+        let system_cfg_state_table: Rc<Table<SystemCfgState, u32>> =
+            router_status.parse_table(&DOCS_IF31_CM_SYSTEM_CFG_STATE_TABLE)?;
+        let system_cfg_state = system_cfg_state_table
+            .iter()
+            .min_by_key(|(index, _)| *index)
+            .map(|(_, system_cfg_state)| system_cfg_state);
+        self.set_diplexer(system_cfg_state);
+
+        // Indexed by ifIndex and SFID
+        let qos_service_flow_table: Rc<Table<QOSServiceFlow, (u32, u32)>> =
             router_status.parse_table(&DOCS_QOS_SERVICE_FLOW_TABLE)?;
 
-        let qos_param_set_table: Table<QOSParamSet> =
+        let qos_param_set_table: Rc<Table<QOSParamSet, (u32, u32)>> =
             router_status.parse_table(&DOCS_QOS_PARAM_SET_TABLE)?;
 
-        let primary_downstream_flow_index = qos_service_flow_table
-            .iter()
-            .filter(|(_, entry)| {
-                entry.direction == QOSServiceFlowDirection::Downstream && entry.primary
-            })
-            .map(|(key, _)| key)
-            .next()
-            .context("primary downstream flow key not found")?;
-
-        let primary_downstream_param_set = qos_param_set_table
-            .get(primary_downstream_flow_index)
-            .context("primary downstream param set not found")?;
-
-        self.primary_downstream_sfid.set(
-            primary_downstream_flow_index
-                .split('.')
-                .next_back()
-                .context("unable to extract primary downstream SFID")?
-                .parse()?,
-        );
+        // Dynamic flows (e.g. voice calls) come and go, so start from a clean slate each scrape
+        self.reset();
+
+        let mut primary_downstream = None;
+        let mut primary_upstream = None;
+
+        for (index, service_flow) in qos_service_flow_table.iter() {
+            let sfid = index.1.to_string();
+            let param_set = qos_param_set_table.get(index);
+
+            let direction = service_flow.direction.label();
+            let scheduling_type = param_set.map_or("".into(), |p| p.scheduling_type.label());
+            let labels = [
+                sfid.as_str(),
+                &direction,
+                if service_flow.primary {
+                    "true"
+                } else {
+                    "false"
+                },
+                &scheduling_type,
+            ];
+
+            self.service_flow_info.with_label_values(&labels).set(1);
+
+            let param_set = match param_set {
+                Some(param_set) => param_set,
+                None => {
+                    log::debug!("no param set found for service flow: {}", sfid);
+                    continue;
+                }
+            };
+
+            if service_flow.primary {
+                match service_flow.direction.known() {
+                    Some(QOSServiceFlowDirection::Downstream) => {
+                        primary_downstream = Some((index.1, param_set))
+                    }
+                    Some(QOSServiceFlowDirection::Upstream) => {
+                        primary_upstream = Some((index.1, param_set))
+                    }
+                    None => {}
+                }
+            }
+
+            let set = |gauge: &UIntGaugeVec, value: Option<u64>| {
+                if let Some(value) = value {
+                    gauge.with_label_values(&labels).set(value);
+                }
+            };
+
+            set(&self.service_flow_priority, param_set.priority);
+            set(
+                &self.service_flow_max_traffic_rate,
+                Some(param_set.max_traffic_rate),
+            );
+            set(
+                &self.service_flow_max_traffic_burst,
+                Some(param_set.max_traffic_burst),
+            );
+            set(
+                &self.service_flow_min_reserved_rate,
+                Some(param_set.min_reserved_rate),
+            );
+            set(
+                &self.service_flow_min_reserved_pkt,
+                param_set.min_reserved_pkt,
+            );
+            set(&self.service_flow_active_timeout, param_set.active_timeout);
+            set(
+                &self.service_flow_admitted_timeout,
+                param_set.admitted_timeout,
+            );
+            set(
+                &self.service_flow_max_concat_burst,
+                Some(param_set.max_concat_burst),
+            );
+            self.service_flow_scheduling_type
+                .with_label_values(&labels)
+                .set(param_set.scheduling_type.code());
+            set(
+                &self.service_flow_nom_poll_interval,
+                param_set.nom_poll_interval,
+            );
+            set(
+                &self.service_flow_tol_poll_jitter,
+                param_set.tol_poll_jitter,
+            );
+            set(
+                &self.service_flow_unsolicit_grant_size,
+                param_set.unsolicit_grant_size,
+            );
+            set(
+                &self.service_flow_nom_grant_interval,
+                param_set.nom_grant_interval,
+            );
+            set(
+                &self.service_flow_tol_grant_jitter,
+                param_set.tol_grant_jitter,
+            );
+            set(
+                &self.service_flow_grants_per_interval,
+                param_set.grants_per_interval,
+            );
+            set(&self.service_flow_max_latency, param_set.max_latency);
+        }
+
+        self.set_primary_flows(primary_downstream, primary_upstream);
+
+        let qos_service_flow_stats_table: Rc<Table<QOSServiceFlowStats, (u32, u32)>> =
+            router_status.parse_table(&DOCS_QOS_SERVICE_FLOW_STATS_TABLE)?;
+
+        let mut stats_labels = HashSet::new();
+
+        let service_flow_stats = qos_service_flow_stats_table
+            .by_ref()
+            .join(&qos_service_flow_table);
+
+        for (index, (stats, service_flow)) in service_flow_stats.iter() {
+            let sfid = index.1.to_string();
+            let direction = service_flow.direction.label();
+            let labels = [sfid.as_str(), &direction];
+
+            set_counter(
+                &self.service_flow_packets.with_label_values(&labels),
+                stats.pkts,
+            );
+            set_counter(
+                &self.service_flow_bytes.with_label_values(&labels),
+                stats.octets,
+            );
+            set_counter(
+                &self
+                    .service_flow_policed_drop_packets
+                    .with_label_values(&labels),
+                stats.policed_drop_pkts,
+            );
+            set_counter(
+                &self
+                    .service_flow_policed_delay_packets
+                    .with_label_values(&labels),
+                stats.policed_delay_pkts,
+            );
+
+            stats_labels.insert([labels[0].to_owned(), labels[1].to_owned()]);
+        }
+
+        // Counters can't be reset every scrape like the gauges, so drop the flows that have gone
+        let mut previous_stats_labels = self.service_flow_stats_labels.lock().unwrap();
+        for labels in previous_stats_labels.difference(&stats_labels) {
+            let labels = [labels[0].as_str(), labels[1].as_str()];
+            let _ = self.service_flow_packets.remove_label_values(&labels);
+            let _ = self.service_flow_bytes.remove_label_values(&labels);
+            let _ = self
+                .service_flow_policed_drop_packets
+                .remove_label_values(&labels);
+            let _ = self
+                .service_flow_policed_delay_packets
+                .remove_label_values(&labels);
+        }
+        *previous_stats_labels = stats_labels;
+
+        Ok(())
+    }
 
-        self.primary_downstream_max_traffic_rate
-            .set(primary_downstream_param_set.max_traffic_rate);
-        self.primary_downstream_max_traffic_burst
-            .set(primary_downstream_param_set.max_traffic_burst);
-        self.primary_downstream_min_reserved_rate
-            .set(primary_downstream_param_set.min_reserved_rate);
+    fn set_diplexer(&self, system_cfg_state: Option<&SystemCfgState>) {
+        reset_rows(&[&self.diplexer_capability, &self.diplexer_config]);
+
+        let mut upstream_upper_edge = None;
+        if let Some(system_cfg_state) = system_cfg_state {
+            for (bit, (band, upper_edge)) in DIPLEXER_BANDS.iter().enumerate() {
+                if let Some(capability) = &system_cfg_state.diplexer_capability {
+                    self.diplexer_capability
+                        .with_label_values(&[band])
+                        .set(capability.is_set(bit) as u64);
+                }
+                if let Some(cfg) = &system_cfg_state.diplexer_cfg {
+                    self.diplexer_config
+                        .with_label_values(&[band])
+                        .set(cfg.is_set(bit) as u64);
+                    if cfg.is_set(bit) {
+                        upstream_upper_edge = Some(*upper_edge);
+                    }
+                }
+            }
+        }
+
+        // Left out without a configured band, as 0 Hz would read as a real band edge
+        set_optional_gauge(&self.diplexer_upstream_upper_edge, upstream_upper_edge);
+    }
 
-        let primary_upstream_flow_index = qos_service_flow_table
-            .iter()
-            .filter(|(_, entry)| {
-                entry.direction == QOSServiceFlowDirection::Upstream && entry.primary
-            })
-            .map(|(key, _)| key)
-            .next()
-            .context("primary upstream flow key not found")?;
-
-        let primary_upstream_param_set = qos_param_set_table
-            .get(primary_upstream_flow_index)
-            .context("primary upstream param set not found")?;
-
-        self.primary_upstream_sfid.set(
-            primary_upstream_flow_index
-                .split('.')
-                .next_back()
-                .context("unable to extract primary downstream SFID")?
-                .parse()?,
+    /// Sets the primary flow metrics, which predate the per-flow ones and are kept for existing
+    /// dashboards. Each flow is its SFID and parameter set, and its metrics are left out when the
+    /// hub doesn't have one.
+    fn set_primary_flows(
+        &self,
+        downstream: Option<(u32, &QOSParamSet)>,
+        upstream: Option<(u32, &QOSParamSet)>,
+    ) {
+        let sfid = |flow: Option<(u32, &QOSParamSet)>| flow.map(|(sfid, _)| u64::from(sfid));
+        let param = |flow: Option<(u32, &QOSParamSet)>, value: fn(&QOSParamSet) -> u64| {
+            flow.map(|(_, param_set)| value(param_set))
+        };
+
+        set_optional_gauge(&self.primary_downstream_sfid, sfid(downstream));
+        set_optional_gauge(
+            &self.primary_downstream_max_traffic_rate,
+            param(downstream, |p| p.max_traffic_rate),
+        );
+        set_optional_gauge(
+            &self.primary_downstream_max_traffic_burst,
+            param(downstream, |p| p.max_traffic_burst),
+        );
+        set_optional_gauge(
+            &self.primary_downstream_min_reserved_rate,
+            param(downstream, |p| p.min_reserved_rate),
         );
 
-        self.primary_upstream_max_traffic_rate
-            .set(primary_upstream_param_set.max_traffic_rate);
-        self.primary_upstream_max_traffic_burst
-            .set(primary_upstream_param_set.max_traffic_burst);
-        self.primary_upstream_min_reserved_rate
-            .set(primary_upstream_param_set.min_reserved_rate);
-        self.primary_upstream_max_concat_burst
-            .set(primary_upstream_param_set.max_concat_burst);
-        self.primary_upstream_scheduling_type
-            .set(primary_upstream_param_set.scheduling_type as u64);
+        set_optional_gauge(&self.primary_upstream_sfid, sfid(upstream));
+        set_optional_gauge(
+            &self.primary_upstream_max_traffic_rate,
+            param(upstream, |p| p.max_traffic_rate),
+        );
+        set_optional_gauge(
+            &self.primary_upstream_max_traffic_burst,
+            param(upstream, |p| p.max_traffic_burst),
+        );
+        set_optional_gauge(
+            &self.primary_upstream_min_reserved_rate,
+            param(upstream, |p| p.min_reserved_rate),
+        );
+        set_optional_gauge(
+            &self.primary_upstream_max_concat_burst,
+            param(upstream, |p| p.max_concat_burst),
+        );
+        set_optional_gauge(
+            &self.primary_upstream_scheduling_type,
+            upstream.map(|(_, param_set)| param_set.scheduling_type.code()),
+        );
 
-        Ok(())
+        // Cleared by `reset` along with the per-flow metrics
+        if let Some((sfid, param_set)) = upstream {
+            self.primary_upstream_service_flow_info
+                .with_label_values(&[&sfid.to_string(), &param_set.scheduling_type.label()])
+                .set(1);
+        }
+    }
+
+    fn reset(&self) {
+        reset_rows(&[
+            &self.primary_upstream_service_flow_info,
+            &self.service_flow_info,
+            &self.service_flow_priority,
+            &self.service_flow_max_traffic_rate,
+            &self.service_flow_max_traffic_burst,
+            &self.service_flow_min_reserved_rate,
+            &self.service_flow_min_reserved_pkt,
+            &self.service_flow_active_timeout,
+            &self.service_flow_admitted_timeout,
+            &self.service_flow_max_concat_burst,
+            &self.service_flow_scheduling_type,
+            &self.service_flow_nom_poll_interval,
+            &self.service_flow_tol_poll_jitter,
+            &self.service_flow_unsolicit_grant_size,
+            &self.service_flow_nom_grant_interval,
+            &self.service_flow_tol_grant_jitter,
+            &self.service_flow_grants_per_interval,
+            &self.service_flow_max_latency,
+        ]);
     }
 }
 
@@ -252,6 +686,9 @@ impl Collector for ConfigurationMetrics {
     fn desc(&self) -> Vec<&Desc> {
         vec![
             self.docsis_mode.desc(),
+            self.diplexer_capability.desc(),
+            self.diplexer_config.desc(),
+            self.diplexer_upstream_upper_edge.desc(),
             self.primary_downstream_sfid.desc(),
             self.primary_downstream_max_traffic_rate.desc(),
             self.primary_downstream_max_traffic_burst.desc(),
@@ -262,6 +699,28 @@ impl Collector for ConfigurationMetrics {
             self.primary_upstream_min_reserved_rate.desc(),
             self.primary_upstream_max_concat_burst.desc(),
             self.primary_upstream_scheduling_type.desc(),
+            self.primary_upstream_service_flow_info.desc(),
+            self.service_flow_info.desc(),
+            self.service_flow_priority.desc(),
+            self.service_flow_max_traffic_rate.desc(),
+            self.service_flow_max_traffic_burst.desc(),
+            self.service_flow_min_reserved_rate.desc(),
+            self.service_flow_min_reserved_pkt.desc(),
+            self.service_flow_active_timeout.desc(),
+            self.service_flow_admitted_timeout.desc(),
+            self.service_flow_max_concat_burst.desc(),
+            self.service_flow_scheduling_type.desc(),
+            self.service_flow_nom_poll_interval.desc(),
+            self.service_flow_tol_poll_jitter.desc(),
+            self.service_flow_unsolicit_grant_size.desc(),
+            self.service_flow_nom_grant_interval.desc(),
+            self.service_flow_tol_grant_jitter.desc(),
+            self.service_flow_grants_per_interval.desc(),
+            self.service_flow_max_latency.desc(),
+            self.service_flow_packets.desc(),
+            self.service_flow_bytes.desc(),
+            self.service_flow_policed_drop_packets.desc(),
+            self.service_flow_policed_delay_packets.desc(),
         ]
         .into_iter()
         .flatten()
@@ -271,6 +730,9 @@ impl Collector for ConfigurationMetrics {
     fn collect(&self) -> Vec<MetricFamily> {
         vec![
             self.docsis_mode.collect(),
+            self.diplexer_capability.collect(),
+            self.diplexer_config.collect(),
+            self.diplexer_upstream_upper_edge.collect(),
             self.primary_downstream_sfid.collect(),
             self.primary_downstream_max_traffic_rate.collect(),
             self.primary_downstream_max_traffic_burst.collect(),
@@ -281,9 +743,105 @@ impl Collector for ConfigurationMetrics {
             self.primary_upstream_min_reserved_rate.collect(),
             self.primary_upstream_max_concat_burst.collect(),
             self.primary_upstream_scheduling_type.collect(),
+            self.primary_upstream_service_flow_info.collect(),
+            self.service_flow_info.collect(),
+            self.service_flow_priority.collect(),
+            self.service_flow_max_traffic_rate.collect(),
+            self.service_flow_max_traffic_burst.collect(),
+            self.service_flow_min_reserved_rate.collect(),
+            self.service_flow_min_reserved_pkt.collect(),
+            self.service_flow_active_timeout.collect(),
+            self.service_flow_admitted_timeout.collect(),
+            self.service_flow_max_concat_burst.collect(),
+            self.service_flow_scheduling_type.collect(),
+            self.service_flow_nom_poll_interval.collect(),
+            self.service_flow_tol_poll_jitter.collect(),
+            self.service_flow_unsolicit_grant_size.collect(),
+            self.service_flow_nom_grant_interval.collect(),
+            self.service_flow_tol_grant_jitter.collect(),
+            self.service_flow_grants_per_interval.collect(),
+            self.service_flow_max_latency.collect(),
+            self.service_flow_packets.collect(),
+            self.service_flow_bytes.collect(),
+            self.service_flow_policed_drop_packets.collect(),
+            self.service_flow_policed_delay_packets.collect(),
         ]
         .into_iter()
         .flatten()
         .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::series;
+
+    const FLOW: &str = r#"
+        "1.3.6.1.2.1.10.127.1.1.5.0": "5",
+        "1.3.6.1.4.1.4491.2.1.21.1.3.1.7.2.10": "1",
+        "1.3.6.1.4.1.4491.2.1.21.1.3.1.8.2.10": "1""#;
+    const PARAM_SET: &str = r#",
+        "1.3.6.1.4.1.4491.2.1.21.1.2.1.6.2.10": "350000000",
+        "1.3.6.1.4.1.4491.2.1.21.1.2.1.7.2.10": "42600",
+        "1.3.6.1.4.1.4491.2.1.21.1.2.1.8.2.10": "0",
+        "1.3.6.1.4.1.4491.2.1.21.1.2.1.12.2.10": "0",
+        "1.3.6.1.4.1.4491.2.1.21.1.2.1.13.2.10": "2""#;
+
+    fn set(configuration_metrics: &ConfigurationMetrics, values: &str) {
+        let router_status = List::from_reader(
+            format!("{{{}}}", values).as_bytes(),
+            ConfigurationMetrics::SUBTREES,
+        )
+        .unwrap();
+        configuration_metrics.set(&router_status).unwrap();
+    }
+
+    #[test]
+    fn primary_flow_is_cleared() {
+        let configuration_metrics = ConfigurationMetrics::new().unwrap();
+
+        set(&configuration_metrics, &[FLOW, PARAM_SET].concat());
+        assert_eq!(
+            series(&configuration_metrics.primary_downstream_sfid),
+            [("".to_owned(), 10.0)].into()
+        );
+        assert_eq!(
+            series(&configuration_metrics.primary_downstream_max_traffic_rate),
+            [("".to_owned(), 350_000_000.0)].into()
+        );
+
+        // The parameter set has gone, so there's nothing to report for the flow
+        set(&configuration_metrics, FLOW);
+        assert!(series(&configuration_metrics.primary_downstream_sfid).is_empty());
+        assert!(series(&configuration_metrics.primary_downstream_max_traffic_rate).is_empty());
+    }
+
+    fn stats(pkts: u64) -> String {
+        format!(
+            r#",
+            "1.3.6.1.4.1.4491.2.1.21.1.4.1.1.2.10": "{}",
+            "1.3.6.1.4.1.4491.2.1.21.1.4.1.2.2.10": "1000",
+            "1.3.6.1.4.1.4491.2.1.21.1.4.1.6.2.10": "0",
+            "1.3.6.1.4.1.4491.2.1.21.1.4.1.7.2.10": "0""#,
+            pkts
+        )
+    }
+
+    #[test]
+    fn counters_restart_when_the_hub_resets() {
+        let configuration_metrics = ConfigurationMetrics::new().unwrap();
+        let packets =
+            || series(&configuration_metrics.service_flow_packets)["direction=downstream,sfid=10"];
+
+        set(&configuration_metrics, &[FLOW, &stats(100)].concat());
+        assert_eq!(packets(), 100.0);
+
+        set(&configuration_metrics, &[FLOW, &stats(150)].concat());
+        assert_eq!(packets(), 150.0);
+
+        // The hub's counter went backwards, e.g. after a reboot
+        set(&configuration_metrics, &[FLOW, &stats(20)].concat());
+        assert_eq!(packets(), 20.0);
+    }
+}
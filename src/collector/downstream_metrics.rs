@@ -1,21 +1,22 @@
-use super::UIntGaugeVec;
-use crate::snmp::{List, Table, TableEntry, OID};
+use super::{count_missing_row, reset_rows, ChannelLabels, UIntCounterVec, UIntGaugeVec};
+use crate::snmp::{oid, EnumValue, Enumeration, List, Table, TableRow, OID};
 
-use anyhow::{bail, Context, Result};
+use anyhow::Result;
 use prometheus::{
     core::{Collector, Desc},
     proto::MetricFamily,
-    GaugeVec, Opts,
+    GaugeVec, IntGaugeVec, Opts,
 };
-use std::{convert::TryFrom, str::FromStr};
+use std::rc::Rc;
 
-const DOCS_IF_DOWNSTREAM_CHANNEL_TABLE: OID = OID::new("1.3.6.1.2.1.10.127.1.1.1"); // docsIfDownstreamChannelTable
-const DOCS_IF_DOWN_CHANNEL_ID: OID = OID::new("1.3.6.1.2.1.10.127.1.1.1.1.1"); // docsIfDownChannelId
-const DOCS_IF_DOWN_CHANNEL_FREQUENCY: OID = OID::new("1.3.6.1.2.1.10.127.1.1.1.1.2"); // docsIfDownChannelFrequency
-const DOCS_IF_DOWN_CHANNEL_MODULATION: OID = OID::new("1.3.6.1.2.1.10.127.1.1.1.1.4"); // docsIfDownChannelModulation
-const DOCS_IF_DOWN_CHANNEL_POWER: OID = OID::new("1.3.6.1.2.1.10.127.1.1.1.1.6"); // docsIfDownChannelPower
+const DOCS_IF_DOWNSTREAM_CHANNEL_TABLE: OID = oid!("1.3.6.1.2.1.10.127.1.1.1"); // docsIfDownstreamChannelTable
+const DOCS_IF_DOWN_CHANNEL_ID: OID = oid!("1.3.6.1.2.1.10.127.1.1.1.1.1"); // docsIfDownChannelId
+const DOCS_IF_DOWN_CHANNEL_FREQUENCY: OID = oid!("1.3.6.1.2.1.10.127.1.1.1.1.2"); // docsIfDownChannelFrequency
+const DOCS_IF_DOWN_CHANNEL_MODULATION: OID = oid!("1.3.6.1.2.1.10.127.1.1.1.1.4"); // docsIfDownChannelModulation
+const DOCS_IF_DOWN_CHANNEL_POWER: OID = oid!("1.3.6.1.2.1.10.127.1.1.1.1.6"); // docsIfDownChannelPower
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Enumeration)]
+#[snmp(name = "docsIfDownChannelModulation")]
 pub enum DownstreamModulation {
     Unknown = 1,
     Other = 2,
@@ -23,89 +24,50 @@ pub enum DownstreamModulation {
     QAM256 = 4,
 }
 
-impl FromStr for DownstreamModulation {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use DownstreamModulation::*;
-
-        Ok(match s {
-            "1" => Unknown,
-            "2" => Other,
-            "3" => QAM64,
-            "4" => QAM256,
-            _ => bail!("unknown downstream modulation"),
-        })
-    }
-}
-
-#[derive(Debug)]
-struct DownstreamChannel {
+#[derive(Clone, Debug, TableRow)]
+pub struct DownstreamChannel {
+    #[snmp(column = DOCS_IF_DOWN_CHANNEL_ID)]
     down_channel_id: u64,
-    down_channel_frequency: u64,
-    down_channel_modulation: DownstreamModulation,
+    #[snmp(column = DOCS_IF_DOWN_CHANNEL_FREQUENCY)]
+    pub down_channel_frequency: u64,
+    #[snmp(column = DOCS_IF_DOWN_CHANNEL_MODULATION)]
+    down_channel_modulation: EnumValue<DownstreamModulation>,
+    #[snmp(column = DOCS_IF_DOWN_CHANNEL_POWER, kind = integer, scale = 10)]
     down_channel_power: f64,
 }
 
-impl TryFrom<TableEntry> for DownstreamChannel {
-    type Error = anyhow::Error;
+const DOCS_IF3_SIGNAL_QUALITY_EXT_TABLE: OID = oid!("1.3.6.1.4.1.4491.2.1.20.1.24"); // docsIf3SignalQualityExtTable
+const DOCS_IF3_SIGNAL_QUALITY_EXT_RX_MER: OID = oid!("1.3.6.1.4.1.4491.2.1.20.1.24.1.1"); // docsIf3SignalQualityExtRxMER
 
-    fn try_from(entry: TableEntry) -> Result<Self, Self::Error> {
-        Ok(DownstreamChannel {
-            down_channel_id: entry.parse_column(&DOCS_IF_DOWN_CHANNEL_ID)?,
-            down_channel_frequency: entry.parse_column(&DOCS_IF_DOWN_CHANNEL_FREQUENCY)?,
-            down_channel_modulation: entry.parse_column(&DOCS_IF_DOWN_CHANNEL_MODULATION)?,
-            down_channel_power: entry.parse_column::<f64>(&DOCS_IF_DOWN_CHANNEL_POWER)? / 10.0,
-        })
-    }
-}
-
-const DOCS_IF3_SIGNAL_QUALITY_EXT_TABLE: OID = OID::new("1.3.6.1.4.1.4491.2.1.20.1.24"); // docsIf3SignalQualityExtTable
-const DOCS_IF3_SIGNAL_QUALITY_EXT_RX_MER: OID = OID::new("1.3.6.1.4.1.4491.2.1.20.1.24.1.1"); // docsIf3SignalQualityExtRxMER
-
-#[derive(Debug)]
+#[derive(Clone, Debug, TableRow)]
 struct SignalQualityExt {
+    #[snmp(column = DOCS_IF3_SIGNAL_QUALITY_EXT_RX_MER, kind = integer, scale = 10)]
     rx_mer: f64,
 }
 
-impl TryFrom<TableEntry> for SignalQualityExt {
-    type Error = anyhow::Error;
-
-    fn try_from(entry: TableEntry) -> Result<Self, Self::Error> {
-        Ok(SignalQualityExt {
-            rx_mer: entry.parse_column::<f64>(&DOCS_IF3_SIGNAL_QUALITY_EXT_RX_MER)? / 10.0,
-        })
-    }
-}
-
-const DOCS_IF_SIGNAL_QUALITY_TABLE: OID = OID::new("1.3.6.1.2.1.10.127.1.1.4"); // docsIfSignalQualityTable
-const DOCS_IF_SIG_QCORRECTEDS: OID = OID::new("1.3.6.1.2.1.10.127.1.1.4.1.3"); // docsIfSigQCorrecteds
-const DOCS_IF_SIG_QUNCORRECTABLES: OID = OID::new("1.3.6.1.2.1.10.127.1.1.4.1.4"); // docsIfSigQUncorrectables
-const DOCS_IF_SIG_QSIGNAL_NOISE: OID = OID::new("1.3.6.1.2.1.10.127.1.1.4.1.5"); // docsIfSigQSignalNoise
+const DOCS_IF_SIGNAL_QUALITY_TABLE: OID = oid!("1.3.6.1.2.1.10.127.1.1.4"); // docsIfSignalQualityTable
+const DOCS_IF_SIG_QCORRECTEDS: OID = oid!("1.3.6.1.2.1.10.127.1.1.4.1.3"); // docsIfSigQCorrecteds
+const DOCS_IF_SIG_QUNCORRECTABLES: OID = oid!("1.3.6.1.2.1.10.127.1.1.4.1.4"); // docsIfSigQUncorrectables
+const DOCS_IF_SIG_QSIGNAL_NOISE: OID = oid!("1.3.6.1.2.1.10.127.1.1.4.1.5"); // docsIfSigQSignalNoise
 
-#[derive(Debug)]
+#[derive(Clone, Debug, TableRow)]
 struct SignalQuality {
+    #[snmp(column = DOCS_IF_SIG_QCORRECTEDS, kind = counter32)]
     correcteds: u64,
+    #[snmp(column = DOCS_IF_SIG_QUNCORRECTABLES, kind = counter32)]
     uncorrectables: u64,
+    #[snmp(column = DOCS_IF_SIG_QSIGNAL_NOISE, scale = 10)]
     signal_noise: u64,
 }
 
-impl TryFrom<TableEntry> for SignalQuality {
-    type Error = anyhow::Error;
-
-    fn try_from(entry: TableEntry) -> Result<Self, Self::Error> {
-        Ok(SignalQuality {
-            correcteds: entry.parse_column(&DOCS_IF_SIG_QCORRECTEDS)?,
-            uncorrectables: entry.parse_column(&DOCS_IF_SIG_QUNCORRECTABLES)?,
-            signal_noise: entry.parse_column::<u64>(&DOCS_IF_SIG_QSIGNAL_NOISE)? / 10,
-        })
-    }
-}
-
 pub struct DownstreamMetrics {
+    channel_labels: ChannelLabels,
+    missing_table_rows: UIntCounterVec,
+
+    down_channel_info: UIntGaugeVec,
     down_channel_id: UIntGaugeVec,
     down_channel_frequency: UIntGaugeVec,
-    down_channel_modulation: UIntGaugeVec,
+    down_channel_modulation: IntGaugeVec,
     down_channel_power: GaugeVec,
     down_channel_rx_mer: GaugeVec,
     down_channel_correcteds: UIntGaugeVec,
@@ -114,110 +76,166 @@ pub struct DownstreamMetrics {
 }
 
 impl DownstreamMetrics {
-    pub fn new() -> Result<Self> {
+    pub const SUBTREES: &'static [OID] = &[
+        DOCS_IF_DOWNSTREAM_CHANNEL_TABLE,
+        DOCS_IF3_SIGNAL_QUALITY_EXT_TABLE,
+        DOCS_IF_SIGNAL_QUALITY_TABLE,
+    ];
+
+    pub fn new(channel_labels: ChannelLabels, missing_table_rows: UIntCounterVec) -> Result<Self> {
         Ok(DownstreamMetrics {
+            channel_labels,
+            missing_table_rows,
+
+            down_channel_info: UIntGaugeVec::new(
+                Opts::new("down_channel_info", "Downstream Channel Info"),
+                &["index", "channel_id", "frequency_hz", "modulation"],
+            )?,
             down_channel_id: UIntGaugeVec::new(
                 Opts::new("down_channel_id", "Downstream Channel ID"),
-                &["index"],
+                channel_labels.names(),
             )?,
             down_channel_frequency: UIntGaugeVec::new(
                 Opts::new(
                     "down_channel_frequency",
                     "Downstream Channel Frequency (Hz)",
                 ),
-                &["index"],
+                channel_labels.names(),
             )?,
-            down_channel_modulation: UIntGaugeVec::new(
+            down_channel_modulation: IntGaugeVec::new(
                 Opts::new("down_channel_modulation", "Downstream Channel Modulation"),
-                &["index"],
+                channel_labels.names(),
             )?,
             down_channel_power: GaugeVec::new(
                 Opts::new("down_channel_power", "Downstream Channel Power (dBmV)"),
-                &["index"],
+                channel_labels.names(),
             )?,
             down_channel_rx_mer: GaugeVec::new(
                 Opts::new("down_channel_rx_mer", "Downstream Channel RxMER (dB)"),
-                &["index"],
+                channel_labels.names(),
             )?,
             down_channel_correcteds: UIntGaugeVec::new(
                 Opts::new(
                     "down_channel_correcteds",
                     "Downstream Channel Pre RS Errors",
                 ),
-                &["index"],
+                channel_labels.names(),
             )?,
             down_channel_uncorrectables: UIntGaugeVec::new(
                 Opts::new(
                     "down_channel_uncorrectables",
                     "Downstream Channel Post RS Errors",
                 ),
-                &["index"],
+                channel_labels.names(),
             )?,
             down_channel_signal_noise: UIntGaugeVec::new(
                 Opts::new("down_channel_signal_noise", "Downstream Channel SNR (dB)"),
-                &["index"],
+                channel_labels.names(),
             )?,
         })
     }
 
     pub fn set(&self, router_status: &List) -> Result<()> {
-        let downstream_channel_table: Table<DownstreamChannel> =
+        let downstream_channel_table: Rc<Table<DownstreamChannel>> =
             router_status.parse_table(&DOCS_IF_DOWNSTREAM_CHANNEL_TABLE)?;
-        let signal_quality_ext_table: Table<SignalQualityExt> =
+        let signal_quality_ext_table: Rc<Table<SignalQualityExt>> =
             router_status.parse_table(&DOCS_IF3_SIGNAL_QUALITY_EXT_TABLE)?;
-        let signal_quality_table: Table<SignalQuality> =
+        let signal_quality_table: Rc<Table<SignalQuality>> =
             router_status.parse_table(&DOCS_IF_SIGNAL_QUALITY_TABLE)?;
 
-        for (index, downstream_channel_entry) in downstream_channel_table.iter() {
-            let signal_quality_ext_entry = signal_quality_ext_table.get(index).context(format!(
-                "failed to find signal quality ext entry for index: {}",
-                index
-            ))?;
-            let signal_quality_entry = signal_quality_table.get(index).context(format!(
-                "failed to find signal quality entry for index: {}",
-                index
-            ))?;
+        self.reset();
+
+        let channels = downstream_channel_table
+            .by_ref()
+            .left_join(&signal_quality_ext_table)
+            .left_join(&signal_quality_table);
+
+        for (index, ((downstream_channel_entry, signal_quality_ext_entry), signal_quality_entry)) in
+            channels.iter()
+        {
+            let labels = self.channel_labels.values(
+                index,
+                downstream_channel_entry.down_channel_id,
+                downstream_channel_entry.down_channel_frequency,
+            );
+            let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+
+            self.down_channel_info
+                .with_label_values(&[
+                    index,
+                    &downstream_channel_entry.down_channel_id.to_string(),
+                    &downstream_channel_entry.down_channel_frequency.to_string(),
+                    &downstream_channel_entry.down_channel_modulation.label(),
+                ])
+                .set(1);
 
             self.down_channel_id
-                .with_label_values(&[index])
+                .with_label_values(&labels)
                 .set(downstream_channel_entry.down_channel_id);
 
             self.down_channel_frequency
-                .with_label_values(&[index])
+                .with_label_values(&labels)
                 .set(downstream_channel_entry.down_channel_frequency);
 
             self.down_channel_modulation
-                .with_label_values(&[index])
-                .set(downstream_channel_entry.down_channel_modulation as u64);
+                .with_label_values(&labels)
+                .set(downstream_channel_entry.down_channel_modulation.code());
 
             self.down_channel_power
-                .with_label_values(&[index])
+                .with_label_values(&labels)
                 .set(downstream_channel_entry.down_channel_power);
 
-            self.down_channel_rx_mer
-                .with_label_values(&[index])
-                .set(signal_quality_ext_entry.rx_mer);
-
-            self.down_channel_correcteds
-                .with_label_values(&[index])
-                .set(signal_quality_entry.correcteds);
-
-            self.down_channel_uncorrectables
-                .with_label_values(&[index])
-                .set(signal_quality_entry.uncorrectables);
-
-            self.down_channel_signal_noise
-                .with_label_values(&[index])
-                .set(signal_quality_entry.signal_noise);
+            if let Some(signal_quality_ext_entry) = count_missing_row(
+                &self.missing_table_rows,
+                &DOCS_IF3_SIGNAL_QUALITY_EXT_TABLE,
+                *signal_quality_ext_entry,
+            ) {
+                self.down_channel_rx_mer
+                    .with_label_values(&labels)
+                    .set(signal_quality_ext_entry.rx_mer);
+            }
+
+            if let Some(signal_quality_entry) = count_missing_row(
+                &self.missing_table_rows,
+                &DOCS_IF_SIGNAL_QUALITY_TABLE,
+                *signal_quality_entry,
+            ) {
+                self.down_channel_correcteds
+                    .with_label_values(&labels)
+                    .set(signal_quality_entry.correcteds);
+
+                self.down_channel_uncorrectables
+                    .with_label_values(&labels)
+                    .set(signal_quality_entry.uncorrectables);
+
+                self.down_channel_signal_noise
+                    .with_label_values(&labels)
+                    .set(signal_quality_entry.signal_noise);
+            }
         }
 
         Ok(())
     }
+
+    fn reset(&self) {
+        reset_rows(&[
+            &self.down_channel_info,
+            &self.down_channel_id,
+            &self.down_channel_frequency,
+            &self.down_channel_modulation,
+            &self.down_channel_power,
+            &self.down_channel_rx_mer,
+            &self.down_channel_correcteds,
+            &self.down_channel_uncorrectables,
+            &self.down_channel_signal_noise,
+        ]);
+    }
 }
 
 impl Collector for DownstreamMetrics {
     fn desc(&self) -> Vec<&Desc> {
         vec![
+            self.down_channel_info.desc(),
             self.down_channel_id.desc(),
             self.down_channel_frequency.desc(),
             self.down_channel_modulation.desc(),
@@ -234,6 +252,7 @@ impl Collector for DownstreamMetrics {
 
     fn collect(&self) -> Vec<MetricFamily> {
         vec![
+            self.down_channel_info.collect(),
             self.down_channel_id.collect(),
             self.down_channel_frequency.collect(),
             self.down_channel_modulation.collect(),
@@ -248,3 +267,52 @@ impl Collector for DownstreamMetrics {
         .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::series;
+
+    #[test]
+    fn missing_signal_quality_row() {
+        let router_status = List::from_reader(
+            r#"{
+                "1.3.6.1.2.1.10.127.1.1.1.1.1.3": "5",
+                "1.3.6.1.2.1.10.127.1.1.1.1.2.3": "331000000",
+                "1.3.6.1.2.1.10.127.1.1.1.1.4.3": "4",
+                "1.3.6.1.2.1.10.127.1.1.1.1.6.3": "35",
+                "1.3.6.1.4.1.4491.2.1.20.1.24.1.1.3": "400"
+            }"#
+            .as_bytes(),
+            DownstreamMetrics::SUBTREES,
+        )
+        .unwrap();
+
+        let missing_table_rows = UIntCounterVec::new(
+            prometheus::Opts::new(
+                "missing_table_rows_total",
+                "Rows Missing From A Joined Table",
+            ),
+            &["table"],
+        )
+        .unwrap();
+        let downstream_metrics =
+            DownstreamMetrics::new(ChannelLabels::Index, missing_table_rows.clone()).unwrap();
+        downstream_metrics.set(&router_status).unwrap();
+
+        // The channel is still exported with what the other tables have for it
+        assert_eq!(
+            series(&downstream_metrics.down_channel_power),
+            [("index=3".to_owned(), 3.5)].into()
+        );
+        assert_eq!(
+            series(&downstream_metrics.down_channel_rx_mer),
+            [("index=3".to_owned(), 40.0)].into()
+        );
+        assert!(series(&downstream_metrics.down_channel_correcteds).is_empty());
+        assert_eq!(
+            series(&missing_table_rows),
+            [("table=1.3.6.1.2.1.10.127.1.1.4".to_owned(), 1.0)].into()
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use super::{
+    interface_metrics::{
+        interface_speed, IfOperStatus, Interface, InterfaceExtended, IF_TABLE, IF_X_TABLE,
+    },
+    reset_rows, UIntGaugeVec,
+};
+use crate::snmp::{oid, EnumValue, Enumeration, List, Table, TableRow, OID};
+
+use anyhow::Result;
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    Opts,
+};
+use std::rc::Rc;
+
+const ARRIS_ROUTER_ETHERNET_PORT_TABLE: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.2.11"); // arrisRouterEthernetPortTable
+const ARRIS_ROUTER_ETHERNET_PORT_IF_INDEX: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.2.11.1.2"); // arrisRouterEthernetPortIfIndex
+const ARRIS_ROUTER_ETHERNET_PORT_DUPLEX: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.2.11.1.4"); // arrisRouterEthernetPortDuplex
+
+#[derive(Clone, Copy, Debug, Enumeration)]
+#[snmp(name = "arrisRouterEthernetPortDuplex")]
+enum EthernetPortDuplex {
+    Half = 1,
+    Full = 2,
+}
+
+#[derive(Clone, Debug, TableRow)]
+struct EthernetPort {
+    #[snmp(column = ARRIS_ROUTER_ETHERNET_PORT_IF_INDEX)]
+    if_index: u32,
+    #[snmp(column = ARRIS_ROUTER_ETHERNET_PORT_DUPLEX)]
+    duplex: Option<EnumValue<EthernetPortDuplex>>,
+}
+
+pub struct EthernetPortMetrics {
+    ethernet_port_up: UIntGaugeVec,
+    ethernet_port_speed: UIntGaugeVec,
+    ethernet_port_duplex: UIntGaugeVec,
+}
+
+impl EthernetPortMetrics {
+    pub const SUBTREES: &'static [OID] = &[ARRIS_ROUTER_ETHERNET_PORT_TABLE, IF_TABLE, IF_X_TABLE];
+
+    pub fn new() -> Result<Self> {
+        Ok(EthernetPortMetrics {
+            ethernet_port_up: UIntGaugeVec::new(
+                Opts::new("ethernet_port_up", "Ethernet Port Link Up"),
+                &["port"],
+            )?,
+            ethernet_port_speed: UIntGaugeVec::new(
+                Opts::new("ethernet_port_speed", "Ethernet Port Link Speed (bps)"),
+                &["port"],
+            )?,
+            ethernet_port_duplex: UIntGaugeVec::new(
+                Opts::new("ethernet_port_duplex", "Ethernet Port Duplex"),
+                &["port", "duplex"],
+            )?,
+        })
+    }
+
+    pub fn set(&self, router_status: &List) -> Result<()> {
+        let ethernet_port_table: Rc<Table<EthernetPort>> =
+            router_status.parse_table(&ARRIS_ROUTER_ETHERNET_PORT_TABLE)?;
+        let interface_table: Rc<Table<Interface, u32>> = router_status.parse_table(&IF_TABLE)?;
+        let interface_ext_table: Rc<Table<InterfaceExtended, u32>> =
+            router_status.parse_table(&IF_X_TABLE)?;
+
+        reset_rows(&[
+            &self.ethernet_port_up,
+            &self.ethernet_port_speed,
+            &self.ethernet_port_duplex,
+        ]);
+
+        for (port, ethernet_port) in ethernet_port_table.iter() {
+            let interface = match interface_table.get(&ethernet_port.if_index) {
+                Some(interface) => interface,
+                None => {
+                    log::debug!("no interface found for ethernet port: {}", port);
+                    continue;
+                }
+            };
+            let up = interface.oper_status.known() == Some(IfOperStatus::Up);
+
+            self.ethernet_port_up
+                .with_label_values(&[port])
+                .set(up as u64);
+
+            // The switch reports its last negotiated speed even with the link down
+            let speed = interface_speed(
+                interface.speed,
+                interface_ext_table
+                    .get(&ethernet_port.if_index)
+                    .and_then(|interface_ext| interface_ext.high_speed),
+            );
+            self.ethernet_port_speed
+                .with_label_values(&[port])
+                .set(if up { speed } else { 0 });
+
+            let duplex = match ethernet_port.duplex.and_then(EnumValue::known) {
+                Some(duplex) if up => duplex.label(),
+                _ => "unknown",
+            };
+            for state in EthernetPortDuplex::LABELS.iter().chain(&["unknown"]) {
+                self.ethernet_port_duplex
+                    .with_label_values(&[port, state])
+                    .set((*state == duplex) as u64);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Collector for EthernetPortMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![
+            self.ethernet_port_up.desc(),
+            self.ethernet_port_speed.desc(),
+            self.ethernet_port_duplex.desc(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        vec![
+            self.ethernet_port_up.collect(),
+            self.ethernet_port_speed.collect(),
+            self.ethernet_port_duplex.collect(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
@@ -0,0 +1,228 @@
+use super::UIntCounterVec;
+use crate::client::VirginHubClient;
+use crate::snmp::{oid, DateAndTime, EnumValue, Enumeration, List, Table, TableRow, OID};
+
+use anyhow::Result;
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    Opts,
+};
+use std::{collections::HashMap, rc::Rc, sync::Mutex};
+
+const DOCS_DEV_EVENT_TABLE: OID = oid!("1.3.6.1.2.1.69.1.5.8"); // docsDevEventTable
+const DOCS_DEV_EV_FIRST_TIME: OID = oid!("1.3.6.1.2.1.69.1.5.8.1.2"); // docsDevEvFirstTime
+const DOCS_DEV_EV_LAST_TIME: OID = oid!("1.3.6.1.2.1.69.1.5.8.1.3"); // docsDevEvLastTime
+const DOCS_DEV_EV_COUNTS: OID = oid!("1.3.6.1.2.1.69.1.5.8.1.4"); // docsDevEvCounts
+const DOCS_DEV_EV_LEVEL: OID = oid!("1.3.6.1.2.1.69.1.5.8.1.5"); // docsDevEvLevel
+const DOCS_DEV_EV_ID: OID = oid!("1.3.6.1.2.1.69.1.5.8.1.6"); // docsDevEvId
+const DOCS_DEV_EV_TEXT: OID = oid!("1.3.6.1.2.1.69.1.5.8.1.7"); // docsDevEvText
+
+#[derive(Clone, Copy, Debug, Enumeration)]
+#[snmp(name = "docsDevEvLevel")]
+pub enum EventLevel {
+    Emergency = 1,
+    Alert = 2,
+    Critical = 3,
+    Error = 4,
+    Warning = 5,
+    Notice = 6,
+    Information = 7,
+    Debug = 8,
+}
+
+/// An entry in the hub's DOCSIS event log. Repeats of the same event are coalesced into one
+/// entry, with `count` occurrences between `first_time` and `last_time`.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Event {
+    pub index: u64,
+    pub first_time: Option<i64>,
+    pub last_time: Option<i64>,
+    pub count: u64,
+    pub priority: EnumValue<EventLevel>,
+    pub event_id: u64,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, TableRow)]
+struct EventEntry {
+    #[snmp(column = DOCS_DEV_EV_FIRST_TIME, kind = date_and_time)]
+    first_time: DateAndTime,
+    #[snmp(column = DOCS_DEV_EV_LAST_TIME, kind = date_and_time)]
+    last_time: DateAndTime,
+    #[snmp(column = DOCS_DEV_EV_COUNTS, kind = counter32)]
+    count: u64,
+    #[snmp(column = DOCS_DEV_EV_LEVEL)]
+    priority: EnumValue<EventLevel>,
+    #[snmp(column = DOCS_DEV_EV_ID, kind = gauge32)]
+    event_id: u64,
+    #[snmp(column = DOCS_DEV_EV_TEXT, kind = octet_string)]
+    text: String,
+}
+
+/// Fetches and decodes just the hub's event log, for the `/events` endpoint.
+pub fn fetch_events(client: &VirginHubClient) -> Result<Vec<Event>> {
+    let router_status = client.get_router_status(EventLogMetrics::SUBTREES)?;
+    read_events(&router_status)
+}
+
+/// Decodes the hub's event log, oldest entry first.
+fn read_events(router_status: &List) -> Result<Vec<Event>> {
+    let event_table: Rc<Table<EventEntry, u32>> =
+        router_status.parse_table(&DOCS_DEV_EVENT_TABLE)?;
+
+    let mut events = event_table
+        .iter()
+        .map(|(index, entry)| Event {
+            index: (*index).into(),
+            first_time: entry.first_time.timestamp(),
+            last_time: entry.last_time.timestamp(),
+            count: entry.count,
+            priority: entry.priority,
+            event_id: entry.event_id,
+            text: entry.text.clone(),
+        })
+        .collect::<Vec<_>>();
+    events.sort_by_key(|event| event.index);
+
+    Ok(events)
+}
+
+/// Occurrences of each log entry, keyed by its index and first occurrence.
+type EventCounts = HashMap<(u64, Option<i64>), u64>;
+
+pub struct EventLogMetrics {
+    event_log_entries: UIntCounterVec,
+
+    /// `None` until the first scrape, which only records the counts already in the log
+    event_counts: Mutex<Option<EventCounts>>,
+}
+
+impl EventLogMetrics {
+    pub const SUBTREES: &'static [OID] = &[DOCS_DEV_EVENT_TABLE];
+
+    pub fn new() -> Result<Self> {
+        Ok(EventLogMetrics {
+            event_log_entries: UIntCounterVec::new(
+                Opts::new("event_log_entries_total", "DOCSIS Event Log Entries"),
+                &["priority", "event_id"],
+            )?,
+
+            event_counts: Mutex::new(None),
+        })
+    }
+
+    pub fn set(&self, router_status: &List) -> Result<()> {
+        let events = read_events(router_status)?;
+
+        // Entries keep their index while the hub bumps their count, and indices are reused once
+        // the log wraps, so an entry is identified by its index and first occurrence. Events
+        // logged before the exporter started aren't counted, so the counter starts from 0.
+        let mut previous_event_counts = self.event_counts.lock().unwrap();
+        let mut event_counts = HashMap::new();
+        for event in events.iter() {
+            let key = (event.index, event.first_time);
+            let counter = self
+                .event_log_entries
+                .with_label_values(&[&event.priority.label(), &event.event_id.to_string()]);
+
+            let previous_count = match &*previous_event_counts {
+                Some(previous_event_counts) => {
+                    previous_event_counts.get(&key).copied().unwrap_or_default()
+                }
+                None => event.count,
+            };
+            if event.count > previous_count {
+                counter.inc_by(event.count - previous_count);
+            }
+
+            event_counts.insert(key, event.count);
+        }
+        *previous_event_counts = Some(event_counts);
+
+        Ok(())
+    }
+}
+
+impl Collector for EventLogMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        self.event_log_entries.desc()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        self.event_log_entries.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::series;
+
+    /// A log entry first seen at 2026-10-18 21:02:`second`, in the hub's `DateAndTime` encoding.
+    fn event(index: u32, second: u8, count: u64) -> String {
+        format!(
+            r#""1.3.6.1.2.1.69.1.5.8.1.2.{index}": "$07ea0a121502{second:02x}00",
+            "1.3.6.1.2.1.69.1.5.8.1.3.{index}": "$07ea0a1215030000",
+            "1.3.6.1.2.1.69.1.5.8.1.4.{index}": "{count}",
+            "1.3.6.1.2.1.69.1.5.8.1.5.{index}": "3",
+            "1.3.6.1.2.1.69.1.5.8.1.6.{index}": "82000200",
+            "1.3.6.1.2.1.69.1.5.8.1.7.{index}": "No Ranging Response received""#,
+            index = index,
+            second = second,
+            count = count
+        )
+    }
+
+    fn list(events: &[String]) -> List {
+        List::from_reader(
+            format!("{{{}}}", events.join(",")).as_bytes(),
+            EventLogMetrics::SUBTREES,
+        )
+        .unwrap()
+    }
+
+    fn entries(event_log_metrics: &EventLogMetrics) -> f64 {
+        series(&event_log_metrics.event_log_entries)["event_id=82000200,priority=critical"]
+    }
+
+    #[test]
+    fn events_are_read_in_index_order() {
+        let events = read_events(&list(&[event(10, 4, 1), event(2, 2, 3)])).unwrap();
+
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| (event.index, event.count))
+                .collect::<Vec<_>>(),
+            [(2, 3), (10, 1)]
+        );
+        assert_eq!(events[0].first_time, Some(1_792_357_322));
+        assert_eq!(events[0].text, "No Ranging Response received");
+    }
+
+    #[test]
+    fn only_new_occurrences_are_counted() {
+        let event_log_metrics = EventLogMetrics::new().unwrap();
+
+        // Entries already in the log when the exporter starts aren't counted
+        event_log_metrics.set(&list(&[event(1, 2, 5)])).unwrap();
+        assert_eq!(entries(&event_log_metrics), 0.0);
+
+        event_log_metrics.set(&list(&[event(1, 2, 7)])).unwrap();
+        assert_eq!(entries(&event_log_metrics), 2.0);
+
+        event_log_metrics.set(&list(&[event(1, 2, 7)])).unwrap();
+        assert_eq!(entries(&event_log_metrics), 2.0);
+    }
+
+    #[test]
+    fn reused_indices_are_new_entries() {
+        let event_log_metrics = EventLogMetrics::new().unwrap();
+        event_log_metrics.set(&list(&[event(1, 2, 5)])).unwrap();
+
+        // The log wrapped, and index 1 now holds a later event
+        event_log_metrics.set(&list(&[event(1, 4, 3)])).unwrap();
+        assert_eq!(entries(&event_log_metrics), 3.0);
+    }
+}
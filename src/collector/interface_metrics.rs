@@ -0,0 +1,204 @@
+use super::{set_counter, UIntCounterVec};
+use crate::snmp::{oid, EnumValue, Enumeration, List, Table, TableRow, OID};
+
+use anyhow::Result;
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    Opts,
+};
+use std::{collections::HashSet, rc::Rc, sync::Mutex};
+
+pub(super) const IF_TABLE: OID = oid!("1.3.6.1.2.1.2.2"); // ifTable
+const IF_DESCR: OID = oid!("1.3.6.1.2.1.2.2.1.2"); // ifDescr
+const IF_TYPE: OID = oid!("1.3.6.1.2.1.2.2.1.3"); // ifType
+const IF_SPEED: OID = oid!("1.3.6.1.2.1.2.2.1.5"); // ifSpeed
+const IF_OPER_STATUS: OID = oid!("1.3.6.1.2.1.2.2.1.8"); // ifOperStatus
+const IF_IN_OCTETS: OID = oid!("1.3.6.1.2.1.2.2.1.10"); // ifInOctets
+const IF_OUT_OCTETS: OID = oid!("1.3.6.1.2.1.2.2.1.16"); // ifOutOctets
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Enumeration)]
+#[snmp(name = "ifOperStatus")]
+pub(super) enum IfOperStatus {
+    Up = 1,
+    Down = 2,
+    Testing = 3,
+    Unknown = 4,
+    Dormant = 5,
+    NotPresent = 6,
+    LowerLayerDown = 7,
+}
+
+/// The IANAifType values found on the hub. Other types are labelled with their raw number.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Enumeration)]
+#[snmp(name = "IANAifType")]
+pub(super) enum IfType {
+    Other = 1,
+    EthernetCsmacd = 6,
+    SoftwareLoopback = 24,
+    Ieee80211 = 71,
+    DocsCableMaclayer = 127,
+    DocsCableDownstream = 128,
+    DocsCableUpstream = 129,
+    Tunnel = 131,
+    #[snmp(label = "l2vlan")]
+    L2Vlan = 135,
+    Usb = 160,
+    VoiceOverCable = 198,
+    DocsCableUpstreamChannel = 205,
+    Bridge = 209,
+    DocsOfdmDownstream = 277,
+    DocsOfdmaUpstream = 278,
+}
+
+/// An interface's speed (bps), as ifSpeed saturates at ~4.3 Gbps and ifHighSpeed is in Mbps.
+pub(super) fn interface_speed(speed: u64, high_speed: Option<u64>) -> u64 {
+    match high_speed {
+        Some(high_speed) if high_speed > 0 => high_speed * 1_000_000,
+        _ => speed,
+    }
+}
+
+/// An ifTable row, shared by the collectors that read interfaces so the table is parsed once.
+#[derive(Clone, Debug, TableRow)]
+pub(super) struct Interface {
+    #[snmp(column = IF_DESCR)]
+    pub(super) descr: String,
+    #[snmp(column = IF_TYPE)]
+    pub(super) if_type: EnumValue<IfType>,
+    #[snmp(column = IF_SPEED, kind = gauge32)]
+    pub(super) speed: u64,
+    #[snmp(column = IF_OPER_STATUS)]
+    pub(super) oper_status: EnumValue<IfOperStatus>,
+    #[snmp(column = IF_IN_OCTETS, kind = counter32)]
+    pub(super) in_octets: u64,
+    #[snmp(column = IF_OUT_OCTETS, kind = counter32)]
+    pub(super) out_octets: u64,
+}
+
+pub(super) const IF_X_TABLE: OID = oid!("1.3.6.1.2.1.31.1.1"); // ifXTable
+const IF_NAME: OID = oid!("1.3.6.1.2.1.31.1.1.1.1"); // ifName
+const IF_HC_IN_OCTETS: OID = oid!("1.3.6.1.2.1.31.1.1.1.6"); // ifHCInOctets
+const IF_HC_OUT_OCTETS: OID = oid!("1.3.6.1.2.1.31.1.1.1.10"); // ifHCOutOctets
+const IF_HIGH_SPEED: OID = oid!("1.3.6.1.2.1.31.1.1.1.15"); // ifHighSpeed
+
+#[derive(Clone, Debug, TableRow)]
+pub(super) struct InterfaceExtended {
+    #[snmp(column = IF_NAME, kind = octet_string)]
+    pub(super) name: Option<String>,
+    #[snmp(column = IF_HC_IN_OCTETS, kind = counter64)]
+    pub(super) hc_in_octets: Option<u64>,
+    #[snmp(column = IF_HC_OUT_OCTETS, kind = counter64)]
+    pub(super) hc_out_octets: Option<u64>,
+    #[snmp(column = IF_HIGH_SPEED, kind = gauge32)]
+    pub(super) high_speed: Option<u64>,
+}
+
+const INTERFACE_LABELS: [&str; 5] = ["index", "name", "type", "speed", "oper_status"];
+
+pub struct InterfaceMetrics {
+    interface_receive_bytes: UIntCounterVec,
+    interface_transmit_bytes: UIntCounterVec,
+    interface_labels: Mutex<HashSet<Vec<String>>>,
+}
+
+impl InterfaceMetrics {
+    pub const SUBTREES: &'static [OID] = &[IF_TABLE, IF_X_TABLE];
+
+    pub fn new() -> Result<Self> {
+        Ok(InterfaceMetrics {
+            interface_receive_bytes: UIntCounterVec::new(
+                Opts::new("interface_receive_bytes_total", "Interface Received Bytes"),
+                &INTERFACE_LABELS,
+            )?,
+            interface_transmit_bytes: UIntCounterVec::new(
+                Opts::new(
+                    "interface_transmit_bytes_total",
+                    "Interface Transmitted Bytes",
+                ),
+                &INTERFACE_LABELS,
+            )?,
+            interface_labels: Mutex::new(HashSet::new()),
+        })
+    }
+
+    pub fn set(&self, router_status: &List) -> Result<()> {
+        let interface_table: Rc<Table<Interface, u32>> = router_status.parse_table(&IF_TABLE)?;
+        let interface_ext_table: Rc<Table<InterfaceExtended, u32>> =
+            router_status.parse_table(&IF_X_TABLE)?;
+
+        let mut interface_labels = HashSet::new();
+
+        for (index, interface) in interface_table.iter() {
+            let interface_ext = interface_ext_table.get(index);
+
+            let name = interface_ext
+                .and_then(|e| e.name.as_ref())
+                .filter(|name| !name.is_empty())
+                .unwrap_or(&interface.descr);
+
+            let speed = interface_speed(interface.speed, interface_ext.and_then(|e| e.high_speed));
+
+            let labels = vec![
+                index.to_string(),
+                name.to_owned(),
+                interface.if_type.label().into_owned(),
+                speed.to_string(),
+                interface.oper_status.label().into_owned(),
+            ];
+            let label_values: Vec<&str> = labels.iter().map(String::as_str).collect();
+
+            set_counter(
+                &self
+                    .interface_receive_bytes
+                    .with_label_values(&label_values),
+                interface_ext
+                    .and_then(|e| e.hc_in_octets)
+                    .unwrap_or(interface.in_octets),
+            );
+            set_counter(
+                &self
+                    .interface_transmit_bytes
+                    .with_label_values(&label_values),
+                interface_ext
+                    .and_then(|e| e.hc_out_octets)
+                    .unwrap_or(interface.out_octets),
+            );
+
+            interface_labels.insert(labels);
+        }
+
+        // The speed and status labels change over time, so drop the series that no longer apply
+        let mut previous_interface_labels = self.interface_labels.lock().unwrap();
+        for labels in previous_interface_labels.difference(&interface_labels) {
+            let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+            let _ = self.interface_receive_bytes.remove_label_values(&labels);
+            let _ = self.interface_transmit_bytes.remove_label_values(&labels);
+        }
+        *previous_interface_labels = interface_labels;
+
+        Ok(())
+    }
+}
+
+impl Collector for InterfaceMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![
+            self.interface_receive_bytes.desc(),
+            self.interface_transmit_bytes.desc(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        vec![
+            self.interface_receive_bytes.collect(),
+            self.interface_transmit_bytes.collect(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
@@ -0,0 +1,245 @@
+use super::{UIntGauge, UIntGaugeVec};
+use crate::snmp::{
+    oid, EnumValue, Enumeration, InetAddress, List, MacAddress, Table, TableRow, OID,
+};
+
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    Opts,
+};
+use sha2::Sha256;
+use std::rc::Rc;
+
+const ARRIS_ROUTER_LAN_CLIENT_TABLE: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.2.4.2"); // arrisRouterLanClientTable
+const ARRIS_ROUTER_LAN_CLIENT_HOST_NAME: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.2.4.2.1.3"); // arrisRouterLanClientHostName
+const ARRIS_ROUTER_LAN_CLIENT_MAC_ADDR: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.2.4.2.1.4"); // arrisRouterLanClientMacAddr
+const ARRIS_ROUTER_LAN_CLIENT_ADAPTER_TYPE: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.2.4.2.1.6"); // arrisRouterLanClientAdapterType
+const ARRIS_ROUTER_LAN_CLIENT_ONLINE: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.2.4.2.1.14"); // arrisRouterLanClientOnline
+
+/// How client identifiers (MAC addresses and hostnames) are exported.
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LanClientPrivacy {
+    #[default]
+    None,
+    Hash,
+    Drop,
+}
+
+/// Applies the configured `LanClientPrivacy` to client identifiers.
+#[derive(Clone)]
+pub struct ClientPrivacy {
+    privacy: LanClientPrivacy,
+    hash_key: Vec<u8>,
+}
+
+impl ClientPrivacy {
+    /// Hashes are keyed, as there are too few MAC addresses behind each vendor prefix for an
+    /// unkeyed hash to hide them. Without a `hash_key` a random one is used, so hashes change
+    /// when the exporter restarts.
+    pub fn new(privacy: LanClientPrivacy, hash_key: Option<&str>) -> Self {
+        let hash_key = match hash_key.filter(|hash_key| !hash_key.is_empty()) {
+            Some(hash_key) => hash_key.as_bytes().to_vec(),
+            None => {
+                if let LanClientPrivacy::Hash = privacy {
+                    log::info!(
+                        "no lan_client_hash_key set, so client hashes will change on restart"
+                    );
+                }
+                rand::random::<[u8; 32]>().to_vec()
+            }
+        };
+
+        ClientPrivacy { privacy, hash_key }
+    }
+
+    pub(super) fn apply(&self, value: &str) -> String {
+        match self.privacy {
+            LanClientPrivacy::None => value.to_owned(),
+            LanClientPrivacy::Hash => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(&self.hash_key)
+                    .expect("HMAC takes a key of any length");
+                mac.update(value.as_bytes());
+                mac.finalize().into_bytes()[..8]
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect()
+            }
+            LanClientPrivacy::Drop => String::new(),
+        }
+    }
+
+    /// Whether clients keep an identifier, so that a series per client can be exported.
+    pub(super) fn identifies_clients(&self) -> bool {
+        !matches!(self.privacy, LanClientPrivacy::Drop)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Enumeration)]
+#[snmp(name = "arrisRouterLanClientAdapterType")]
+enum LanClientAdapterType {
+    Ethernet = 1,
+    Usb = 2,
+    Moca = 3,
+    #[snmp(label = "wifi")]
+    WiFi = 4,
+}
+
+#[derive(Clone, Debug, TableRow)]
+struct LanClient {
+    #[snmp(column = ARRIS_ROUTER_LAN_CLIENT_HOST_NAME, kind = octet_string)]
+    host_name: String,
+    #[snmp(column = ARRIS_ROUTER_LAN_CLIENT_MAC_ADDR, kind = mac_address)]
+    mac_addr: MacAddress,
+    #[snmp(column = ARRIS_ROUTER_LAN_CLIENT_ADAPTER_TYPE)]
+    adapter_type: Option<EnumValue<LanClientAdapterType>>,
+    #[snmp(column = ARRIS_ROUTER_LAN_CLIENT_ONLINE, kind = truth_value)]
+    online: Option<bool>,
+}
+
+pub struct LanClientMetrics {
+    privacy: ClientPrivacy,
+
+    lan_client_info: UIntGaugeVec,
+    lan_clients_connected: UIntGauge,
+}
+
+impl LanClientMetrics {
+    pub const SUBTREES: &'static [OID] = &[ARRIS_ROUTER_LAN_CLIENT_TABLE];
+
+    pub fn new(privacy: ClientPrivacy) -> Result<Self> {
+        Ok(LanClientMetrics {
+            privacy,
+
+            lan_client_info: UIntGaugeVec::new(
+                Opts::new("lan_client_info", "LAN Client Info"),
+                &["mac", "ip", "hostname", "interface"],
+            )?,
+            lan_clients_connected: UIntGauge::new(
+                "lan_clients_connected",
+                "Connected LAN Clients",
+            )?,
+        })
+    }
+
+    pub fn set(&self, router_status: &List) -> Result<()> {
+        // The table is empty when the hub is in modem mode
+        let lan_client_table: Rc<Table<LanClient, (u32, InetAddress)>> =
+            router_status.parse_table(&ARRIS_ROUTER_LAN_CLIENT_TABLE)?;
+
+        self.lan_client_info.reset();
+
+        let mut connected = 0;
+
+        for ((_, ip), lan_client) in lan_client_table.iter() {
+            if lan_client.online.unwrap_or(true) {
+                connected += 1;
+            }
+
+            self.lan_client_info
+                .with_label_values(&[
+                    &self.privacy.apply(&lan_client.mac_addr.to_string()),
+                    &ip.to_string(),
+                    &self.privacy.apply(&lan_client.host_name),
+                    &lan_client
+                        .adapter_type
+                        .map_or("".into(), |adapter_type| adapter_type.label()),
+                ])
+                .set(1);
+        }
+
+        self.lan_clients_connected.set(connected);
+
+        Ok(())
+    }
+}
+
+impl Collector for LanClientMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![
+            self.lan_client_info.desc(),
+            self.lan_clients_connected.desc(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        vec![
+            self.lan_client_info.collect(),
+            self.lan_clients_connected.collect(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::series;
+
+    #[test]
+    fn hashed_identifiers() {
+        let privacy = ClientPrivacy::new(LanClientPrivacy::Hash, Some("key"));
+        let hash = privacy.apply("00:11:22:33:44:55");
+
+        assert_eq!(hash.len(), 16);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(privacy.apply("00:11:22:33:44:55"), hash);
+        assert_ne!(privacy.apply("00:11:22:33:44:56"), hash);
+        assert_ne!(
+            ClientPrivacy::new(LanClientPrivacy::Hash, Some("other key"))
+                .apply("00:11:22:33:44:55"),
+            hash
+        );
+    }
+
+    #[test]
+    fn dropped_identifiers() {
+        let privacy = ClientPrivacy::new(LanClientPrivacy::Drop, None);
+
+        assert_eq!(privacy.apply("00:11:22:33:44:55"), "");
+        assert_eq!(privacy.apply("laptop"), "");
+        assert!(!privacy.identifies_clients());
+    }
+
+    #[test]
+    fn dropped_clients_keep_their_address() {
+        let router_status = List::from_reader(
+            r#"{
+                "1.3.6.1.4.1.4115.1.20.1.1.2.4.2.1.3.200.1.4.192.168.0.10": "laptop",
+                "1.3.6.1.4.1.4115.1.20.1.1.2.4.2.1.4.200.1.4.192.168.0.10": "$001122334455",
+                "1.3.6.1.4.1.4115.1.20.1.1.2.4.2.1.6.200.1.4.192.168.0.10": "4",
+                "1.3.6.1.4.1.4115.1.20.1.1.2.4.2.1.14.200.1.4.192.168.0.10": "1",
+                "1.3.6.1.4.1.4115.1.20.1.1.2.4.2.1.3.200.1.4.192.168.0.11": "phone",
+                "1.3.6.1.4.1.4115.1.20.1.1.2.4.2.1.4.200.1.4.192.168.0.11": "$001122334456",
+                "1.3.6.1.4.1.4115.1.20.1.1.2.4.2.1.14.200.1.4.192.168.0.11": "2"
+            }"#
+            .as_bytes(),
+            LanClientMetrics::SUBTREES,
+        )
+        .unwrap();
+        let lan_client_metrics =
+            LanClientMetrics::new(ClientPrivacy::new(LanClientPrivacy::Drop, None)).unwrap();
+        lan_client_metrics.set(&router_status).unwrap();
+
+        assert_eq!(
+            series(&lan_client_metrics.lan_client_info),
+            [
+                (
+                    "hostname=,interface=wifi,ip=192.168.0.10,mac=".to_owned(),
+                    1.0
+                ),
+                ("hostname=,interface=,ip=192.168.0.11,mac=".to_owned(), 1.0),
+            ]
+            .into()
+        );
+        assert_eq!(series(&lan_client_metrics.lan_clients_connected)[""], 1.0);
+    }
+}
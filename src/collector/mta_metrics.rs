@@ -0,0 +1,225 @@
+use super::{
+    interface_metrics::{IfOperStatus, IfType, Interface, IF_TABLE},
+    set_state_set, UIntGaugeVec,
+};
+use crate::snmp::{oid, EnumValue, Enumeration, List, Table, TableRow, OID};
+
+use anyhow::Result;
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    Opts,
+};
+use std::rc::Rc;
+
+const PKTC_MTA_DEV_PROVISIONING_STATE: OID = oid!("1.3.6.1.4.1.4491.2.2.1.1.1.9"); // pktcMtaDevProvisioningState
+
+#[derive(Clone, Copy, Debug, Enumeration)]
+#[snmp(name = "pktcMtaDevProvisioningState")]
+enum MtaProvisioningState {
+    Pass = 1,
+    InProgress = 2,
+    FailConfigFileError = 3,
+    PassWithWarnings = 4,
+    PassWithIncompleteParsing = 5,
+    FailureInternalError = 6,
+    FailOtherReason = 7,
+}
+
+const ARRIS_MTA_DEV_LINE_CARD_TABLE: OID = oid!("1.3.6.1.4.1.4115.1.3.3.1.2.3.6"); // arrisMtaDevLineCardTable
+const ARRIS_MTA_DEV_LINE_CARD_HOOK_STATE: OID = oid!("1.3.6.1.4.1.4115.1.3.3.1.2.3.6.1.3"); // arrisMtaDevLineCardHookState
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Enumeration)]
+#[snmp(name = "arrisMtaDevLineCardHookState")]
+enum HookState {
+    OnHook = 1,
+    OffHook = 2,
+}
+
+#[derive(Clone, Debug, TableRow)]
+struct LineCard {
+    #[snmp(column = ARRIS_MTA_DEV_LINE_CARD_HOOK_STATE)]
+    hook_state: Option<EnumValue<HookState>>,
+}
+
+pub struct MtaMetrics {
+    mta_provisioning_status: UIntGaugeVec,
+    mta_line_up: UIntGaugeVec,
+    mta_line_off_hook: UIntGaugeVec,
+}
+
+impl MtaMetrics {
+    pub const SUBTREES: &'static [OID] = &[
+        PKTC_MTA_DEV_PROVISIONING_STATE,
+        IF_TABLE,
+        ARRIS_MTA_DEV_LINE_CARD_TABLE,
+    ];
+
+    pub fn new() -> Result<Self> {
+        Ok(MtaMetrics {
+            mta_provisioning_status: UIntGaugeVec::new(
+                Opts::new("mta_provisioning_status", "MTA Provisioning State"),
+                &["state"],
+            )?,
+            mta_line_up: UIntGaugeVec::new(
+                Opts::new("mta_line_up", "MTA Telephone Line Up"),
+                &["line"],
+            )?,
+            mta_line_off_hook: UIntGaugeVec::new(
+                Opts::new("mta_line_off_hook", "MTA Telephone Line Off Hook"),
+                &["line"],
+            )?,
+        })
+    }
+
+    pub fn set(&self, router_status: &List) -> Result<()> {
+        let provisioning_state: Option<EnumValue<MtaProvisioningState>> =
+            router_status.parse_optional_scalar(&PKTC_MTA_DEV_PROVISIONING_STATE)?;
+        let interface_table: Rc<Table<Interface, u32>> = router_status.parse_table(&IF_TABLE)?;
+        let line_card_table: Rc<Table<LineCard, u32>> =
+            router_status.parse_table(&ARRIS_MTA_DEV_LINE_CARD_TABLE)?;
+
+        self.mta_provisioning_status.reset();
+        self.mta_line_up.reset();
+        self.mta_line_off_hook.reset();
+
+        // Hubs without voice ports have no MTA
+        if let Some(provisioning_state) = provisioning_state {
+            set_state_set(
+                &self.mta_provisioning_status,
+                &[MtaProvisioningState::LABELS, &["unknown"]].concat(),
+                provisioning_state
+                    .known()
+                    .map_or("unknown", MtaProvisioningState::label),
+            );
+        }
+
+        // Lines are numbered from 1 in ifIndex order
+        let mut voice_interfaces: Vec<(&u32, &Interface)> = interface_table
+            .iter()
+            .filter(|(_, interface)| interface.if_type.known() == Some(IfType::VoiceOverCable))
+            .collect();
+        voice_interfaces.sort_by_key(|(index, _)| *index);
+
+        for (line_number, (_, interface)) in (1..).zip(voice_interfaces) {
+            let line = line_number.to_string();
+
+            self.mta_line_up
+                .with_label_values(&[&line])
+                .set((interface.oper_status.known() == Some(IfOperStatus::Up)) as u64);
+
+            // A hook state the hub adds later is left out rather than guessed at
+            if let Some(hook_state) = line_card_table
+                .get(&line_number)
+                .and_then(|line_card| line_card.hook_state)
+                .and_then(EnumValue::known)
+            {
+                self.mta_line_off_hook
+                    .with_label_values(&[&line])
+                    .set((hook_state == HookState::OffHook) as u64);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Collector for MtaMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![
+            self.mta_provisioning_status.desc(),
+            self.mta_line_up.desc(),
+            self.mta_line_off_hook.desc(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        vec![
+            self.mta_provisioning_status.collect(),
+            self.mta_line_up.collect(),
+            self.mta_line_off_hook.collect(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::series;
+
+    /// An ifTable row of the given type and ifOperStatus.
+    fn interface(index: u32, if_type: u32, oper_status: u32) -> String {
+        [
+            (2, "port".to_owned()),
+            (3, if_type.to_string()),
+            (5, "0".to_owned()),
+            (8, oper_status.to_string()),
+            (10, "0".to_owned()),
+            (16, "0".to_owned()),
+        ]
+        .iter()
+        .map(|(column, value)| format!(r#""1.3.6.1.2.1.2.2.1.{}.{}": "{}""#, column, index, value))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+
+    fn set(values: &[String]) -> MtaMetrics {
+        let router_status = List::from_reader(
+            format!("{{{}}}", values.join(",")).as_bytes(),
+            MtaMetrics::SUBTREES,
+        )
+        .unwrap();
+        let mta_metrics = MtaMetrics::new().unwrap();
+        mta_metrics.set(&router_status).unwrap();
+        mta_metrics
+    }
+
+    #[test]
+    fn lines_are_numbered_in_interface_order() {
+        let mta_metrics = set(&[
+            r#""1.3.6.1.4.1.4491.2.2.1.1.1.9.0": "1""#.to_owned(),
+            interface(1, 6, 1),
+            interface(10, 198, 2),
+            interface(9, 198, 1),
+            r#""1.3.6.1.4.1.4115.1.3.3.1.2.3.6.1.3.1": "2""#.to_owned(),
+            r#""1.3.6.1.4.1.4115.1.3.3.1.2.3.6.1.3.2": "1""#.to_owned(),
+        ]);
+
+        assert_eq!(
+            series(&mta_metrics.mta_line_up),
+            [("line=1".to_owned(), 1.0), ("line=2".to_owned(), 0.0)].into()
+        );
+        assert_eq!(
+            series(&mta_metrics.mta_line_off_hook),
+            [("line=1".to_owned(), 1.0), ("line=2".to_owned(), 0.0)].into()
+        );
+        assert_eq!(
+            series(&mta_metrics.mta_provisioning_status)["state=pass"],
+            1.0
+        );
+    }
+
+    #[test]
+    fn unknown_provisioning_state() {
+        let mta_metrics = set(&[r#""1.3.6.1.4.1.4491.2.2.1.1.1.9.0": "99""#.to_owned()]);
+        let status = series(&mta_metrics.mta_provisioning_status);
+
+        assert_eq!(status["state=unknown"], 1.0);
+        assert_eq!(status.values().sum::<f64>(), 1.0);
+        assert!(series(&mta_metrics.mta_line_up).is_empty());
+    }
+
+    #[test]
+    fn no_mta() {
+        let mta_metrics = set(&[interface(1, 6, 1)]);
+
+        assert!(series(&mta_metrics.mta_provisioning_status).is_empty());
+        assert!(series(&mta_metrics.mta_line_up).is_empty());
+    }
+}
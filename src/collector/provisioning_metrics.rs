@@ -0,0 +1,209 @@
+use super::{
+    log_parse_error,
+    upstream_metrics::{UpstreamChannel, DOCS_IF_UPSTREAM_CHANNEL_TABLE},
+    UIntCounter, UIntGaugeVec,
+};
+use crate::snmp::{oid, InetAddress, List, MacAddress, OctetString, Table, TableRow, OID};
+
+use anyhow::Result;
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    Opts,
+};
+use std::{rc::Rc, sync::Mutex};
+
+const DOCS_IF_CM_MAC_TABLE: OID = oid!("1.3.6.1.2.1.10.127.1.2.1"); // docsIfCmMacTable
+const DOCS_IF_CM_CMTS_ADDRESS: OID = oid!("1.3.6.1.2.1.10.127.1.2.1.1.1"); // docsIfCmCmtsAddress
+
+#[derive(Clone, Debug, TableRow)]
+struct CmMac {
+    #[snmp(column = DOCS_IF_CM_CMTS_ADDRESS, kind = mac_address)]
+    cmts_address: MacAddress,
+}
+
+const DOCS_DEV_SERVER_DHCP: OID = oid!("1.3.6.1.2.1.69.1.4.2"); // docsDevServerDhcp
+const DOCS_DEV_SERVER_TIME: OID = oid!("1.3.6.1.2.1.69.1.4.3"); // docsDevServerTime
+const DOCS_DEV_SERVER_TFTP: OID = oid!("1.3.6.1.2.1.69.1.4.4"); // docsDevServerTftp
+const DOCS_DEV_SERVER_CONFIG_FILE: OID = oid!("1.3.6.1.2.1.69.1.4.5"); // docsDevServerConfigFile
+const DOCS_DEV_SERVER_DHCP_ADDRESS: OID = oid!("1.3.6.1.2.1.69.1.4.7"); // docsDevServerDhcpAddress
+const DOCS_DEV_SERVER_TIME_ADDRESS: OID = oid!("1.3.6.1.2.1.69.1.4.9"); // docsDevServerTimeAddress
+const DOCS_DEV_SERVER_CONFIG_TFTP_ADDRESS: OID = oid!("1.3.6.1.2.1.69.1.4.11"); // docsDevServerConfigTftpAddress
+
+/// Reads a server address, preferring the InetAddress object over its deprecated IPv4-only
+/// predecessor. An address that doesn't parse is logged and left blank.
+fn server_address(router_status: &List, address: &OID, deprecated: &OID) -> String {
+    let address = match log_parse_error(
+        address,
+        router_status.parse_optional_scalar::<InetAddress>(address),
+    ) {
+        Some(address) if !address.0.is_unspecified() => Some(address),
+        _ => log_parse_error(
+            deprecated,
+            router_status.parse_optional_scalar::<InetAddress>(deprecated),
+        ),
+    };
+
+    address.map_or_else(String::new, |address| address.to_string())
+}
+
+pub struct ProvisioningMetrics {
+    provisioning_info: UIntGaugeVec,
+    config_file_changes: UIntCounter,
+
+    config_file: Mutex<Option<String>>,
+}
+
+impl ProvisioningMetrics {
+    pub const SUBTREES: &'static [OID] = &[
+        DOCS_IF_CM_MAC_TABLE,
+        DOCS_DEV_SERVER_DHCP,
+        DOCS_DEV_SERVER_TIME,
+        DOCS_DEV_SERVER_TFTP,
+        DOCS_DEV_SERVER_CONFIG_FILE,
+        DOCS_DEV_SERVER_DHCP_ADDRESS,
+        DOCS_DEV_SERVER_TIME_ADDRESS,
+        DOCS_DEV_SERVER_CONFIG_TFTP_ADDRESS,
+        DOCS_IF_UPSTREAM_CHANNEL_TABLE,
+    ];
+
+    pub fn new() -> Result<Self> {
+        Ok(ProvisioningMetrics {
+            provisioning_info: UIntGaugeVec::new(
+                Opts::new("provisioning_info", "Provisioning Info"),
+                &[
+                    "cmts_mac",
+                    "config_file",
+                    "tftp_server",
+                    "dhcp_server",
+                    "tod_server",
+                    "up_channel_ids",
+                ],
+            )?,
+            config_file_changes: UIntCounter::new(
+                "config_file_changes_total",
+                "Config File Changes",
+            )?,
+
+            config_file: Mutex::new(None),
+        })
+    }
+
+    pub fn set(&self, router_status: &List) -> Result<()> {
+        let cm_mac_table: Rc<Table<CmMac, u32>> =
+            router_status.parse_table(&DOCS_IF_CM_MAC_TABLE)?;
+        let upstream_channel_table: Rc<Table<UpstreamChannel>> =
+            router_status.parse_table(&DOCS_IF_UPSTREAM_CHANNEL_TABLE)?;
+
+        let cmts_mac = cm_mac_table
+            .iter()
+            .min_by_key(|(index, _)| *index)
+            .map_or_else(String::new, |(_, cm_mac)| cm_mac.cmts_address.to_string());
+
+        let config_file = log_parse_error(
+            &DOCS_DEV_SERVER_CONFIG_FILE,
+            router_status.parse_optional_scalar::<OctetString>(&DOCS_DEV_SERVER_CONFIG_FILE),
+        )
+        .map_or_else(String::new, |config_file| config_file.to_string_lossy());
+
+        let tftp_server = server_address(
+            router_status,
+            &DOCS_DEV_SERVER_CONFIG_TFTP_ADDRESS,
+            &DOCS_DEV_SERVER_TFTP,
+        );
+        let dhcp_server = server_address(
+            router_status,
+            &DOCS_DEV_SERVER_DHCP_ADDRESS,
+            &DOCS_DEV_SERVER_DHCP,
+        );
+        let tod_server = server_address(
+            router_status,
+            &DOCS_DEV_SERVER_TIME_ADDRESS,
+            &DOCS_DEV_SERVER_TIME,
+        );
+
+        let mut up_channel_ids = upstream_channel_table
+            .iter()
+            .map(|(_, upstream_channel)| upstream_channel.up_channel_id)
+            .collect::<Vec<_>>();
+        up_channel_ids.sort_unstable();
+        let up_channel_ids = up_channel_ids
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        self.provisioning_info.reset();
+        self.provisioning_info
+            .with_label_values(&[
+                &cmts_mac,
+                &config_file,
+                &tftp_server,
+                &dhcp_server,
+                &tod_server,
+                &up_channel_ids,
+            ])
+            .set(1);
+
+        // A new config file usually means the service tier has changed
+        let mut previous_config_file = self.config_file.lock().unwrap();
+        if matches!(&*previous_config_file, Some(previous) if *previous != config_file) {
+            self.config_file_changes.inc();
+        }
+        *previous_config_file = Some(config_file);
+
+        Ok(())
+    }
+}
+
+impl Collector for ProvisioningMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![
+            self.provisioning_info.desc(),
+            self.config_file_changes.desc(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        vec![
+            self.provisioning_info.collect(),
+            self.config_file_changes.collect(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::series;
+
+    #[test]
+    fn unparseable_servers_are_left_blank() {
+        let router_status = List::from_reader(
+            r#"{
+                "1.3.6.1.2.1.69.1.4.2.0": "10.0.0.1",
+                "1.3.6.1.2.1.69.1.4.5.0": "$abc",
+                "1.3.6.1.2.1.69.1.4.11.0": "$"
+            }"#
+            .as_bytes(),
+            ProvisioningMetrics::SUBTREES,
+        )
+        .unwrap();
+
+        let provisioning_metrics = ProvisioningMetrics::new().unwrap();
+        provisioning_metrics.set(&router_status).unwrap();
+
+        assert_eq!(
+            series(&provisioning_metrics.provisioning_info)
+                .into_keys()
+                .collect::<Vec<_>>(),
+            ["cmts_mac=,config_file=,dhcp_server=10.0.0.1,tftp_server=,tod_server=,up_channel_ids="]
+        );
+    }
+}
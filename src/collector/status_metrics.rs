@@ -1,64 +1,120 @@
-use super::UIntGauge;
-use crate::snmp::{List, OID};
+use super::{
+    downstream_metrics::DownstreamChannel, set_optional_gauge, set_state_set,
+    upstream_metrics::UpstreamChannel, UIntGaugeVec,
+};
+use crate::snmp::{oid, EnumValue, Enumeration, List, Table, OID};
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use prometheus::{
     core::{Collector, Desc},
     proto::MetricFamily,
+    IntGauge, Opts,
 };
+use std::rc::Rc;
 
-const DOCS_IF_DOWNSTREAM_CHANNEL_TABLE: OID = OID::new("1.3.6.1.2.1.10.127.1.1.1"); // docsIfDownstreamChannelTable
-const DOCS_IF_DOWN_CHANNEL_FREQUENCY: OID = OID::new("1.3.6.1.2.1.10.127.1.1.1.1.2"); // docsIfDownChannelFrequency
+const DOCS_IF_DOWNSTREAM_CHANNEL_TABLE: OID = oid!("1.3.6.1.2.1.10.127.1.1.1"); // docsIfDownstreamChannelTable
+const DOCS_IF_UPSTREAM_CHANNEL_TABLE: OID = oid!("1.3.6.1.2.1.10.127.1.1.2"); // docsIfUpstreamChannelTable
 
-const DOCS_IF_UPSTREAM_CHANNEL_TABLE: OID = OID::new("1.3.6.1.2.1.10.127.1.1.2"); // docsIfUpstreamChannelTable
-const DOCS_IF_UP_CHANNEL_FREQUENCY: OID = OID::new("1.3.6.1.2.1.10.127.1.1.2.1.2"); // docsIfUpChannelFrequency
+const ARRIS_CM_DOC30_SW_REGISTRATION_STATE: OID = oid!("1.3.6.1.4.1.4115.1.3.4.1.5.9"); // arrisCmDoc30SwRegistrationState
 
-const ARRIS_CM_DOC30_SW_REGISTRATION_STATE: OID = OID::new("1.3.6.1.4.1.4115.1.3.4.1.5.9"); // arrisCmDoc30SwRegistrationState
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Enumeration)]
+#[snmp(name = "arrisCmDoc30SwRegistrationState")]
+enum RegistrationState {
+    Other = 1,
+    NotReady = 2,
+    NotSynchronized = 3,
+    PhySynchronized = 4,
+    UsParametersAcquired = 5,
+    RangingComplete = 6,
+    DhcpV4Complete = 7,
+    TodEstablished = 8,
+    SecurityEstablished = 9,
+    ConfigFileDownloadComplete = 10,
+    RegistrationComplete = 11,
+    Operational = 12,
+    AccessDenied = 13,
+    EaeInProgress = 14,
+    DhcpV4InProgress = 15,
+    DhcpV6InProgress = 16,
+    DhcpV6Complete = 17,
+    RegistrationInProgress = 18,
+    BpiInit = 19,
+    ForwardingDisabled = 20,
+    DsTopologyResolutionInProgress = 21,
+    RangingInProgress = 22,
+    RfMuteAll = 23,
+}
 
 pub struct StatusMetrics {
-    acquired_down_channel_frequency: UIntGauge,
-    ranged_up_channel_frequency: UIntGauge,
-    provisioning_state: UIntGauge,
+    acquired_down_channel_frequency: UIntGaugeVec,
+    ranged_up_channel_frequency: UIntGaugeVec,
+    provisioning_state: IntGauge,
+    provisioning_status: UIntGaugeVec,
 }
 
 impl StatusMetrics {
+    pub const SUBTREES: &'static [OID] = &[
+        ARRIS_CM_DOC30_SW_REGISTRATION_STATE,
+        DOCS_IF_DOWNSTREAM_CHANNEL_TABLE,
+        DOCS_IF_UPSTREAM_CHANNEL_TABLE,
+    ];
+
     pub fn new() -> Result<Self> {
         Ok(StatusMetrics {
-            acquired_down_channel_frequency: UIntGauge::new(
-                "acquired_down_channel_frequency",
-                "Acquired Downstream Channel (Hz)",
+            acquired_down_channel_frequency: UIntGaugeVec::new(
+                Opts::new(
+                    "acquired_down_channel_frequency",
+                    "Acquired Downstream Channel (Hz)",
+                ),
+                &[],
             )?,
-            ranged_up_channel_frequency: UIntGauge::new(
-                "ranged_up_channel_frequency",
-                "Ranged Upstream Channel (Hz)",
+            ranged_up_channel_frequency: UIntGaugeVec::new(
+                Opts::new(
+                    "ranged_up_channel_frequency",
+                    "Ranged Upstream Channel (Hz)",
+                ),
+                &[],
+            )?,
+            provisioning_state: IntGauge::new("provisioning_state", "Provisioning State")?,
+            provisioning_status: UIntGaugeVec::new(
+                Opts::new("provisioning_status", "Provisioning State Name"),
+                &["state"],
             )?,
-            provisioning_state: UIntGauge::new("provisioning_state", "Provisioning State")?,
         })
     }
 
     pub fn set(&self, router_status: &List) -> Result<()> {
-        let docsis_reg_status =
+        let registration_state: EnumValue<RegistrationState> =
             router_status.parse_scalar(&ARRIS_CM_DOC30_SW_REGISTRATION_STATE)?;
-        self.provisioning_state.set(docsis_reg_status);
-
-        let downstream_channel_table =
-            router_status.get_table(&DOCS_IF_DOWNSTREAM_CHANNEL_TABLE)?;
-        let acquired_down_channel_frequency = downstream_channel_table
-            .get("1")
-            .context("missing acquired downstream channel")?
-            .get_column(&DOCS_IF_DOWN_CHANNEL_FREQUENCY)
-            .context("missing acquired downstream channel frequency")?;
-        self.acquired_down_channel_frequency
-            .set(acquired_down_channel_frequency.parse()?);
-
-        let upstream_channel_table = router_status.get_table(&DOCS_IF_UPSTREAM_CHANNEL_TABLE)?;
-        let ranged_up_channel_frequency = upstream_channel_table
-            .get("1")
-            .context("missing ranged upstream channel")?
-            .get_column(&DOCS_IF_UP_CHANNEL_FREQUENCY)
-            .context("missing ranged upstream channel frequency")?;
-        self.ranged_up_channel_frequency
-            .set(ranged_up_channel_frequency.parse()?);
+        self.provisioning_state.set(registration_state.code());
+
+        set_state_set(
+            &self.provisioning_status,
+            &[RegistrationState::LABELS, &["unknown"]].concat(),
+            registration_state
+                .known()
+                .map_or("unknown", RegistrationState::label),
+        );
+
+        // Read as the same types as the channel collectors, so the tables are only parsed once.
+        // A bad row 1 is already counted in parse_errors_total, so its frequency is just left out.
+        let downstream_channel_table: Rc<Table<DownstreamChannel>> =
+            router_status.parse_table(&DOCS_IF_DOWNSTREAM_CHANNEL_TABLE)?;
+        set_optional_gauge(
+            &self.acquired_down_channel_frequency,
+            downstream_channel_table
+                .get("1")
+                .map(|acquired_down_channel| acquired_down_channel.down_channel_frequency),
+        );
+
+        let upstream_channel_table: Rc<Table<UpstreamChannel>> =
+            router_status.parse_table(&DOCS_IF_UPSTREAM_CHANNEL_TABLE)?;
+        set_optional_gauge(
+            &self.ranged_up_channel_frequency,
+            upstream_channel_table
+                .get("1")
+                .map(|ranged_up_channel| ranged_up_channel.up_channel_frequency),
+        );
 
         Ok(())
     }
@@ -70,6 +126,7 @@ impl Collector for StatusMetrics {
             self.acquired_down_channel_frequency.desc(),
             self.ranged_up_channel_frequency.desc(),
             self.provisioning_state.desc(),
+            self.provisioning_status.desc(),
         ]
         .into_iter()
         .flatten()
@@ -81,9 +138,45 @@ impl Collector for StatusMetrics {
             self.acquired_down_channel_frequency.collect(),
             self.ranged_up_channel_frequency.collect(),
             self.provisioning_state.collect(),
+            self.provisioning_status.collect(),
         ]
         .into_iter()
         .flatten()
         .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::series;
+
+    fn set(json: &str) -> StatusMetrics {
+        let router_status = List::from_reader(json.as_bytes(), StatusMetrics::SUBTREES).unwrap();
+        let status_metrics = StatusMetrics::new().unwrap();
+        status_metrics.set(&router_status).unwrap();
+        status_metrics
+    }
+
+    #[test]
+    fn operational() {
+        let status_metrics = set(r#"{"1.3.6.1.4.1.4115.1.3.4.1.5.9.0": "12"}"#);
+
+        assert_eq!(series(&status_metrics.provisioning_state)[""], 12.0);
+        assert_eq!(
+            series(&status_metrics.provisioning_status)["state=operational"],
+            1.0
+        );
+    }
+
+    #[test]
+    fn negative_state_is_unknown() {
+        let status_metrics = set(r#"{"1.3.6.1.4.1.4115.1.3.4.1.5.9.0": "-1"}"#);
+
+        assert_eq!(series(&status_metrics.provisioning_state)[""], -1.0);
+        assert_eq!(
+            series(&status_metrics.provisioning_status)["state=unknown"],
+            1.0
+        );
+    }
+}
@@ -1,71 +1,49 @@
-use super::UIntGaugeVec;
-use crate::snmp::{List, Table, TableEntry, OID};
+use super::{count_missing_row, reset_rows, ChannelLabels, UIntCounterVec, UIntGaugeVec};
+use crate::snmp::{oid, EnumValue, Enumeration, List, Table, TableRow, OID};
 
-use anyhow::{bail, Context, Result};
+use anyhow::Result;
 use prometheus::{
     core::{Collector, Desc},
     proto::MetricFamily,
-    GaugeVec, Opts,
+    GaugeVec, IntGaugeVec, Opts,
 };
-use std::{convert::TryFrom, str::FromStr};
+use std::rc::Rc;
 
-const DOCS_IF_UPSTREAM_CHANNEL_TABLE: OID = OID::new("1.3.6.1.2.1.10.127.1.1.2"); // docsIfUpstreamChannelTable
-const DOCS_IF_UP_CHANNEL_ID: OID = OID::new("1.3.6.1.2.1.10.127.1.1.2.1.1"); // docsIfUpChannelId
-const DOCS_IF_UP_CHANNEL_FREQUENCY: OID = OID::new("1.3.6.1.2.1.10.127.1.1.2.1.2"); // docsIfUpChannelFrequency
-const DOCS_IF_UP_CHANNEL_TYPE: OID = OID::new("1.3.6.1.2.1.10.127.1.1.2.1.15"); // docsIfUpChannelType
+pub(super) const DOCS_IF_UPSTREAM_CHANNEL_TABLE: OID = oid!("1.3.6.1.2.1.10.127.1.1.2"); // docsIfUpstreamChannelTable
+const DOCS_IF_UP_CHANNEL_ID: OID = oid!("1.3.6.1.2.1.10.127.1.1.2.1.1"); // docsIfUpChannelId
+const DOCS_IF_UP_CHANNEL_FREQUENCY: OID = oid!("1.3.6.1.2.1.10.127.1.1.2.1.2"); // docsIfUpChannelFrequency
+const DOCS_IF_UP_CHANNEL_TYPE: OID = oid!("1.3.6.1.2.1.10.127.1.1.2.1.15"); // docsIfUpChannelType
 
-#[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, Enumeration)]
+#[snmp(name = "docsIfUpChannelType")]
 pub enum UpstreamChannelType {
+    Unknown = 0,
     TDMA = 1,
     ATDMA = 2,
     SCDMA = 3,
-    #[allow(non_camel_case_types)]
-    TDDMA_ATDMA = 4,
+    #[snmp(label = "tdma_and_atdma")]
+    TDMAAndATDMA = 4,
 }
 
-impl FromStr for UpstreamChannelType {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use UpstreamChannelType::*;
-
-        Ok(match s {
-            "1" => TDMA,
-            "2" => ATDMA,
-            "3" => SCDMA,
-            "4" => TDDMA_ATDMA,
-            _ => bail!("unknown upstream channel type"),
-        })
-    }
-}
-
-#[derive(Debug)]
-struct UpstreamChannel {
-    up_channel_id: u64,
-    up_channel_frequency: u64,
-    up_channel_type: f64,
-}
-
-impl TryFrom<TableEntry> for UpstreamChannel {
-    type Error = anyhow::Error;
-
-    fn try_from(entry: TableEntry) -> Result<Self, Self::Error> {
-        Ok(UpstreamChannel {
-            up_channel_id: entry.parse_column(&DOCS_IF_UP_CHANNEL_ID)?,
-            up_channel_frequency: entry.parse_column(&DOCS_IF_UP_CHANNEL_FREQUENCY)?,
-            up_channel_type: entry.parse_column(&DOCS_IF_UP_CHANNEL_TYPE)?,
-        })
-    }
+#[derive(Clone, Debug, TableRow)]
+pub struct UpstreamChannel {
+    #[snmp(column = DOCS_IF_UP_CHANNEL_ID)]
+    pub up_channel_id: u64,
+    #[snmp(column = DOCS_IF_UP_CHANNEL_FREQUENCY)]
+    pub up_channel_frequency: u64,
+    #[snmp(column = DOCS_IF_UP_CHANNEL_TYPE)]
+    up_channel_type: EnumValue<UpstreamChannelType>,
 }
 
-const ARRIS_CM_DOC30_IF_UPSTREAM_CHANNEL_EXTENDED_TABLE: OID =
-    OID::new("1.3.6.1.4.1.4115.1.3.4.1.9.2"); // arrisCmDoc30IfUpstreamChannelExtendedTable
+const ARRIS_CM_DOC30_IF_UPSTREAM_CHANNEL_EXTENDED_TABLE: OID = oid!("1.3.6.1.4.1.4115.1.3.4.1.9.2"); // arrisCmDoc30IfUpstreamChannelExtendedTable
 const AR_CM_DOC30_IF_UP_CHANNEL_EXTENDED_SYMBOL_RATE: OID =
-    OID::new("1.3.6.1.4.1.4115.1.3.4.1.9.2.1.2"); // arrisCmDoc30IfUpChannelExtendedSymbolRate
-const AR_CM_DOC30_IF_UP_CHANNEL_EXTENDED_MODULATION: OID =
-    OID::new("1.3.6.1.4.1.4115.1.3.4.1.9.2.1.3"); // arrisCmDoc30IfUpChannelExtendedModulation
+    oid!("1.3.6.1.4.1.4115.1.3.4.1.9.2.1.2"); // arrisCmDoc30IfUpChannelExtendedSymbolRate
+const AR_CM_DOC30_IF_UP_CHANNEL_EXTENDED_MODULATION: OID = oid!("1.3.6.1.4.1.4115.1.3.4.1.9.2.1.3"); // arrisCmDoc30IfUpChannelExtendedModulation
 
-#[derive(Clone, Copy, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, Enumeration)]
+#[snmp(name = "arrisCmDoc30IfUpChannelExtendedModulation")]
 pub enum UpstreamChannelModulation {
     QPSK = 1,
     QAM8 = 2,
@@ -76,177 +54,204 @@ pub enum UpstreamChannelModulation {
     QAM256 = 7,
 }
 
-impl FromStr for UpstreamChannelModulation {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use UpstreamChannelModulation::*;
-
-        Ok(match s {
-            "1" => QPSK,
-            "2" => QAM8,
-            "3" => QAM16,
-            "4" => QAM32,
-            "5" => QAM64,
-            "6" => QAM128,
-            "7" => QAM256,
-            _ => bail!("unknown upstream channel modulation"),
-        })
-    }
-}
-
-#[derive(Debug)]
+#[derive(Clone, Debug, TableRow)]
 struct UpstreamChannelExtended {
+    #[snmp(column = AR_CM_DOC30_IF_UP_CHANNEL_EXTENDED_SYMBOL_RATE)]
     symbol_rate: u64,
-    modulation: UpstreamChannelModulation,
-}
-
-impl TryFrom<TableEntry> for UpstreamChannelExtended {
-    type Error = anyhow::Error;
-
-    fn try_from(entry: TableEntry) -> Result<Self, Self::Error> {
-        Ok(UpstreamChannelExtended {
-            symbol_rate: entry.parse_column(&AR_CM_DOC30_IF_UP_CHANNEL_EXTENDED_SYMBOL_RATE)?,
-            modulation: entry.parse_column(&AR_CM_DOC30_IF_UP_CHANNEL_EXTENDED_MODULATION)?,
-        })
-    }
+    #[snmp(column = AR_CM_DOC30_IF_UP_CHANNEL_EXTENDED_MODULATION)]
+    modulation: EnumValue<UpstreamChannelModulation>,
 }
 
-const DOCS_IF3_CM_STATUS_US_TABLE: OID = OID::new("1.3.6.1.4.1.4491.2.1.20.1.2"); // docsIf3CmStatusUsTable
-const DOCS_IF3_CM_STATUS_US_TX_POWER: OID = OID::new("1.3.6.1.4.1.4491.2.1.20.1.2.1.1"); // docsIf3CmStatusUsTxPower
-const DOCS_IF3_CM_STATUS_US_T3_TIMEOUTS: OID = OID::new("1.3.6.1.4.1.4491.2.1.20.1.2.1.2"); // docsIf3CmStatusUsT3Timeouts
-const DOCS_IF3_CM_STATUS_US_T4_TIMEOUTS: OID = OID::new("1.3.6.1.4.1.4491.2.1.20.1.2.1.3"); // docsIf3CmStatusUsT4Timeouts
+const DOCS_IF3_CM_STATUS_US_TABLE: OID = oid!("1.3.6.1.4.1.4491.2.1.20.1.2"); // docsIf3CmStatusUsTable
+const DOCS_IF3_CM_STATUS_US_TX_POWER: OID = oid!("1.3.6.1.4.1.4491.2.1.20.1.2.1.1"); // docsIf3CmStatusUsTxPower
+const DOCS_IF3_CM_STATUS_US_T3_TIMEOUTS: OID = oid!("1.3.6.1.4.1.4491.2.1.20.1.2.1.2"); // docsIf3CmStatusUsT3Timeouts
+const DOCS_IF3_CM_STATUS_US_T4_TIMEOUTS: OID = oid!("1.3.6.1.4.1.4491.2.1.20.1.2.1.3"); // docsIf3CmStatusUsT4Timeouts
 
-#[derive(Debug)]
+#[derive(Clone, Debug, TableRow)]
 struct CmStatusUs {
+    #[snmp(column = DOCS_IF3_CM_STATUS_US_TX_POWER, kind = integer, scale = 10)]
     tx_power: f64,
+    #[snmp(column = DOCS_IF3_CM_STATUS_US_T3_TIMEOUTS, kind = counter32)]
     t3_timeouts: u64,
+    #[snmp(column = DOCS_IF3_CM_STATUS_US_T4_TIMEOUTS, kind = counter32)]
     t4_timeouts: u64,
 }
 
-impl TryFrom<TableEntry> for CmStatusUs {
-    type Error = anyhow::Error;
-
-    fn try_from(entry: TableEntry) -> Result<Self, Self::Error> {
-        Ok(CmStatusUs {
-            tx_power: entry.parse_column::<f64>(&DOCS_IF3_CM_STATUS_US_TX_POWER)? / 10.0,
-            t3_timeouts: entry.parse_column(&DOCS_IF3_CM_STATUS_US_T3_TIMEOUTS)?,
-            t4_timeouts: entry.parse_column(&DOCS_IF3_CM_STATUS_US_T4_TIMEOUTS)?,
-        })
-    }
-}
-
 pub struct UpstreamMetrics {
+    channel_labels: ChannelLabels,
+    missing_table_rows: UIntCounterVec,
+
+    up_channel_info: UIntGaugeVec,
     up_channel_id: UIntGaugeVec,
     up_channel_frequency: UIntGaugeVec,
-    up_channel_type: UIntGaugeVec,
+    up_channel_type: IntGaugeVec,
     up_channel_symbol_rate: UIntGaugeVec,
-    up_channel_modulation: UIntGaugeVec,
+    up_channel_modulation: IntGaugeVec,
     up_channel_tx_power: GaugeVec,
     up_channel_t3_timeouts: UIntGaugeVec,
     up_channel_t4_timeouts: UIntGaugeVec,
 }
 
 impl UpstreamMetrics {
-    pub fn new() -> Result<Self> {
+    pub const SUBTREES: &'static [OID] = &[
+        DOCS_IF_UPSTREAM_CHANNEL_TABLE,
+        ARRIS_CM_DOC30_IF_UPSTREAM_CHANNEL_EXTENDED_TABLE,
+        DOCS_IF3_CM_STATUS_US_TABLE,
+    ];
+
+    pub fn new(channel_labels: ChannelLabels, missing_table_rows: UIntCounterVec) -> Result<Self> {
         Ok(UpstreamMetrics {
+            channel_labels,
+            missing_table_rows,
+
+            up_channel_info: UIntGaugeVec::new(
+                Opts::new("up_channel_info", "Upstream Channel Info"),
+                &["index", "channel_id", "frequency_hz", "modulation", "type"],
+            )?,
             up_channel_id: UIntGaugeVec::new(
                 Opts::new("up_channel_id", "Upstream Channel ID"),
-                &["index"],
+                channel_labels.names(),
             )?,
             up_channel_frequency: UIntGaugeVec::new(
                 Opts::new("up_channel_frequency", "Upstream Channel Frequency (Hz)"),
-                &["index"],
+                channel_labels.names(),
             )?,
-            up_channel_type: UIntGaugeVec::new(
+            up_channel_type: IntGaugeVec::new(
                 Opts::new("up_channel_type", "Upstream Channel Type"),
-                &["index"],
+                channel_labels.names(),
             )?,
             up_channel_symbol_rate: UIntGaugeVec::new(
                 Opts::new(
                     "up_channel_symbol_rate",
                     "Upstream Channel Symbol Rate (ksps)",
                 ),
-                &["index"],
+                channel_labels.names(),
             )?,
-            up_channel_modulation: UIntGaugeVec::new(
+            up_channel_modulation: IntGaugeVec::new(
                 Opts::new("up_channel_modulation", "Upstream Channel Modulation"),
-                &["index"],
+                channel_labels.names(),
             )?,
             up_channel_tx_power: GaugeVec::new(
                 Opts::new("up_channel_tx_power", "Upstream Channel Power (dBmV)"),
-                &["index"],
+                channel_labels.names(),
             )?,
             up_channel_t3_timeouts: UIntGaugeVec::new(
                 Opts::new("up_channel_t3_timeouts", "Upstream Channel T3 Timeouts"),
-                &["index"],
+                channel_labels.names(),
             )?,
             up_channel_t4_timeouts: UIntGaugeVec::new(
                 Opts::new("up_channel_t4_timeouts", "Upstream Channel T4 Timeouts"),
-                &["index"],
+                channel_labels.names(),
             )?,
         })
     }
 
-    pub fn set<'a>(&self, router_status: &List) -> Result<()> {
-        let upstream_channel_table: Table<UpstreamChannel> =
+    pub fn set(&self, router_status: &List) -> Result<()> {
+        let upstream_channel_table: Rc<Table<UpstreamChannel>> =
             router_status.parse_table(&DOCS_IF_UPSTREAM_CHANNEL_TABLE)?;
-        let upstream_channel_ext_table: Table<UpstreamChannelExtended> =
+        let upstream_channel_ext_table: Rc<Table<UpstreamChannelExtended>> =
             router_status.parse_table(&ARRIS_CM_DOC30_IF_UPSTREAM_CHANNEL_EXTENDED_TABLE)?;
-        let upstream_cm_status_table: Table<CmStatusUs> =
+        let upstream_cm_status_table: Rc<Table<CmStatusUs>> =
             router_status.parse_table(&DOCS_IF3_CM_STATUS_US_TABLE)?;
 
-        for (index, upstream_channel_entry) in upstream_channel_table.iter() {
-            let upstream_channel_ext_entry =
-                upstream_channel_ext_table.get(index).context(format!(
-                    "failed to find upstream channel ext entry for index: {}",
-                    index
-                ))?;
-            let upstream_cm_status_entry = upstream_cm_status_table.get(index).context(format!(
-                "failed to find upstream cm status entry for index: {}",
-                index
-            ))?;
+        self.reset();
+
+        let channels = upstream_channel_table
+            .by_ref()
+            .left_join(&upstream_channel_ext_table)
+            .left_join(&upstream_cm_status_table);
+
+        for (
+            index,
+            ((upstream_channel_entry, upstream_channel_ext_entry), upstream_cm_status_entry),
+        ) in channels.iter()
+        {
+            let upstream_channel_ext_entry = count_missing_row(
+                &self.missing_table_rows,
+                &ARRIS_CM_DOC30_IF_UPSTREAM_CHANNEL_EXTENDED_TABLE,
+                *upstream_channel_ext_entry,
+            );
+
+            let labels = self.channel_labels.values(
+                index,
+                upstream_channel_entry.up_channel_id,
+                upstream_channel_entry.up_channel_frequency,
+            );
+            let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+
+            self.up_channel_info
+                .with_label_values(&[
+                    index,
+                    &upstream_channel_entry.up_channel_id.to_string(),
+                    &upstream_channel_entry.up_channel_frequency.to_string(),
+                    &upstream_channel_ext_entry.map_or("".into(), |ext| ext.modulation.label()),
+                    &upstream_channel_entry.up_channel_type.label(),
+                ])
+                .set(1);
 
             self.up_channel_id
-                .with_label_values(&[index])
+                .with_label_values(&labels)
                 .set(upstream_channel_entry.up_channel_id);
 
             self.up_channel_frequency
-                .with_label_values(&[index])
+                .with_label_values(&labels)
                 .set(upstream_channel_entry.up_channel_frequency);
 
             self.up_channel_type
-                .with_label_values(&[index])
-                .set(upstream_channel_entry.up_channel_type as u64);
-
-            self.up_channel_symbol_rate
-                .with_label_values(&[index])
-                .set(upstream_channel_ext_entry.symbol_rate);
-
-            self.up_channel_modulation
-                .with_label_values(&[index])
-                .set(upstream_channel_ext_entry.modulation as u64);
-
-            self.up_channel_tx_power
-                .with_label_values(&[index])
-                .set(upstream_cm_status_entry.tx_power);
-
-            self.up_channel_t3_timeouts
-                .with_label_values(&[index])
-                .set(upstream_cm_status_entry.t3_timeouts);
-
-            self.up_channel_t4_timeouts
-                .with_label_values(&[index])
-                .set(upstream_cm_status_entry.t4_timeouts);
+                .with_label_values(&labels)
+                .set(upstream_channel_entry.up_channel_type.code());
+
+            if let Some(upstream_channel_ext_entry) = upstream_channel_ext_entry {
+                self.up_channel_symbol_rate
+                    .with_label_values(&labels)
+                    .set(upstream_channel_ext_entry.symbol_rate);
+
+                self.up_channel_modulation
+                    .with_label_values(&labels)
+                    .set(upstream_channel_ext_entry.modulation.code());
+            }
+
+            if let Some(upstream_cm_status_entry) = count_missing_row(
+                &self.missing_table_rows,
+                &DOCS_IF3_CM_STATUS_US_TABLE,
+                *upstream_cm_status_entry,
+            ) {
+                self.up_channel_tx_power
+                    .with_label_values(&labels)
+                    .set(upstream_cm_status_entry.tx_power);
+
+                self.up_channel_t3_timeouts
+                    .with_label_values(&labels)
+                    .set(upstream_cm_status_entry.t3_timeouts);
+
+                self.up_channel_t4_timeouts
+                    .with_label_values(&labels)
+                    .set(upstream_cm_status_entry.t4_timeouts);
+            }
         }
 
         Ok(())
     }
+
+    fn reset(&self) {
+        reset_rows(&[
+            &self.up_channel_info,
+            &self.up_channel_id,
+            &self.up_channel_frequency,
+            &self.up_channel_type,
+            &self.up_channel_symbol_rate,
+            &self.up_channel_modulation,
+            &self.up_channel_tx_power,
+            &self.up_channel_t3_timeouts,
+            &self.up_channel_t4_timeouts,
+        ]);
+    }
 }
 
 impl Collector for UpstreamMetrics {
     fn desc(&self) -> Vec<&Desc> {
         vec![
+            self.up_channel_info.desc(),
             self.up_channel_id.desc(),
             self.up_channel_frequency.desc(),
             self.up_channel_type.desc(),
@@ -263,6 +268,7 @@ impl Collector for UpstreamMetrics {
 
     fn collect(&self) -> Vec<MetricFamily> {
         vec![
+            self.up_channel_info.collect(),
             self.up_channel_id.collect(),
             self.up_channel_frequency.collect(),
             self.up_channel_type.collect(),
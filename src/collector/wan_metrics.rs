@@ -0,0 +1,253 @@
+use super::{set_optional_gauge, set_state_set, UIntCounter, UIntGaugeVec};
+use crate::snmp::{oid, DateAndTime, InetAddress, List, Table, TableRow, OID};
+
+use anyhow::Result;
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    IntGaugeVec, Opts,
+};
+use std::{rc::Rc, sync::Mutex};
+
+const ARRIS_ROUTER_WAN_CURRENT_TABLE: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.1.7.1"); // arrisRouterWanCurrentTable
+const ARRIS_ROUTER_WAN_CURRENT_IP_ADDR: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.1.7.1.1.3"); // arrisRouterWanCurrentIPAddr
+const ARRIS_ROUTER_WAN_CURRENT_GW: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.1.7.1.1.5"); // arrisRouterWanCurrentGW
+const ARRIS_ROUTER_WAN_CURRENT_LEASE_TIME: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.1.7.1.1.6"); // arrisRouterWanCurrentLeaseTime
+const ARRIS_ROUTER_WAN_CURRENT_LEASE_EXPIRE: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.1.7.1.1.7"); // arrisRouterWanCurrentLeaseExpire
+
+#[derive(Clone, Debug, TableRow)]
+struct WanAddress {
+    #[snmp(column = ARRIS_ROUTER_WAN_CURRENT_IP_ADDR, kind = inet_address)]
+    ip_addr: InetAddress,
+    #[snmp(column = ARRIS_ROUTER_WAN_CURRENT_GW, kind = inet_address)]
+    gateway: Option<InetAddress>,
+    #[snmp(column = ARRIS_ROUTER_WAN_CURRENT_LEASE_TIME, kind = gauge32)]
+    lease_time: Option<u64>,
+    #[snmp(column = ARRIS_ROUTER_WAN_CURRENT_LEASE_EXPIRE, kind = date_and_time)]
+    lease_expire: Option<DateAndTime>,
+}
+
+const ARRIS_ROUTER_WAN_DNS_SERVER_TABLE: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.1.11.2"); // arrisRouterWanDNSServerTable
+const ARRIS_ROUTER_WAN_DNS_SERVER_IP_ADDR: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.1.11.2.1.2"); // arrisRouterWanDNSServerIPAddr
+
+#[derive(Clone, Debug, TableRow)]
+struct WanDnsServer {
+    #[snmp(column = ARRIS_ROUTER_WAN_DNS_SERVER_IP_ADDR, kind = inet_address)]
+    ip_addr: InetAddress,
+}
+
+const HUB_MODES: [&str; 2] = ["router", "modem"];
+
+pub struct WanMetrics {
+    hub_mode: UIntGaugeVec,
+    wan_info: UIntGaugeVec,
+    wan_lease_expiry_timestamp_seconds: IntGaugeVec,
+    wan_address_changes: UIntCounter,
+
+    wan_addresses: Mutex<Option<(String, String)>>,
+}
+
+impl WanMetrics {
+    pub const SUBTREES: &'static [OID] = &[
+        ARRIS_ROUTER_WAN_CURRENT_TABLE,
+        ARRIS_ROUTER_WAN_DNS_SERVER_TABLE,
+    ];
+
+    pub fn new() -> Result<Self> {
+        Ok(WanMetrics {
+            hub_mode: UIntGaugeVec::new(Opts::new("hub_mode", "Hub Mode"), &["mode"])?,
+            wan_info: UIntGaugeVec::new(
+                Opts::new("wan_info", "WAN Info"),
+                &["ipv4", "ipv6", "gateway", "dns_servers", "lease_time"],
+            )?,
+            wan_lease_expiry_timestamp_seconds: IntGaugeVec::new(
+                Opts::new(
+                    "wan_lease_expiry_timestamp_seconds",
+                    "WAN DHCP Lease Expiry (Unix timestamp)",
+                ),
+                &[],
+            )?,
+            wan_address_changes: UIntCounter::new(
+                "wan_address_changes_total",
+                "WAN Address Changes",
+            )?,
+
+            wan_addresses: Mutex::new(None),
+        })
+    }
+
+    pub fn set(&self, router_status: &List) -> Result<()> {
+        let wan_address_table: Rc<Table<WanAddress>> =
+            router_status.parse_table(&ARRIS_ROUTER_WAN_CURRENT_TABLE)?;
+        let wan_dns_server_table: Rc<Table<WanDnsServer>> =
+            router_status.parse_table(&ARRIS_ROUTER_WAN_DNS_SERVER_TABLE)?;
+
+        // The router status has no mode setting, but the hub only fills in the WAN table when it
+        // is acting as a router: in modem mode the router is disabled and never gets a lease. A
+        // hub in router mode that has yet to get a lease also reads as modem mode, which is
+        // accurate for anything the WAN metrics would report
+        let wan_addresses: Vec<&WanAddress> = wan_address_table
+            .iter()
+            .map(|(_, wan_address)| wan_address)
+            .filter(|wan_address| !wan_address.ip_addr.0.is_unspecified())
+            .collect();
+
+        if wan_addresses.is_empty() {
+            // Rows that didn't parse may well have had an address, so the mode isn't known
+            if router_status.has_row_errors(&ARRIS_ROUTER_WAN_CURRENT_TABLE) {
+                self.hub_mode.reset();
+            } else {
+                set_state_set(&self.hub_mode, &HUB_MODES, "modem");
+            }
+            self.wan_info.reset();
+            set_optional_gauge(&self.wan_lease_expiry_timestamp_seconds, None);
+            return Ok(());
+        }
+
+        set_state_set(&self.hub_mode, &HUB_MODES, "router");
+
+        let ipv4 = wan_addresses
+            .iter()
+            .find(|wan_address| wan_address.ip_addr.0.is_ipv4());
+        let ipv6 = wan_addresses
+            .iter()
+            .find(|wan_address| wan_address.ip_addr.0.is_ipv6());
+
+        let ipv4_addr = ipv4.map_or_else(String::new, |w| w.ip_addr.to_string());
+        let ipv6_addr = ipv6.map_or_else(String::new, |w| w.ip_addr.to_string());
+
+        let gateway = ipv4
+            .and_then(|w| w.gateway)
+            .filter(|gateway| !gateway.0.is_unspecified())
+            .map_or_else(String::new, |gateway| gateway.to_string());
+
+        let mut dns_servers: Vec<(&String, String)> = wan_dns_server_table
+            .iter()
+            .filter(|(_, dns_server)| !dns_server.ip_addr.0.is_unspecified())
+            .map(|(index, dns_server)| (index, dns_server.ip_addr.to_string()))
+            .collect();
+        dns_servers.sort();
+        let dns_servers = dns_servers
+            .into_iter()
+            .map(|(_, ip_addr)| ip_addr)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let lease_time = ipv4
+            .and_then(|w| w.lease_time)
+            .map_or_else(String::new, |lease_time| lease_time.to_string());
+
+        self.wan_info.reset();
+        self.wan_info
+            .with_label_values(&[&ipv4_addr, &ipv6_addr, &gateway, &dns_servers, &lease_time])
+            .set(1);
+
+        set_optional_gauge(
+            &self.wan_lease_expiry_timestamp_seconds,
+            ipv4.and_then(|w| w.lease_expire)
+                .and_then(|lease_expire| lease_expire.timestamp()),
+        );
+
+        let mut previous_wan_addresses = self.wan_addresses.lock().unwrap();
+        let wan_addresses = (ipv4_addr, ipv6_addr);
+        if matches!(&*previous_wan_addresses, Some(previous) if *previous != wan_addresses) {
+            self.wan_address_changes.inc();
+        }
+        *previous_wan_addresses = Some(wan_addresses);
+
+        Ok(())
+    }
+}
+
+impl Collector for WanMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![
+            self.hub_mode.desc(),
+            self.wan_info.desc(),
+            self.wan_lease_expiry_timestamp_seconds.desc(),
+            self.wan_address_changes.desc(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        vec![
+            self.hub_mode.collect(),
+            self.wan_info.collect(),
+            self.wan_lease_expiry_timestamp_seconds.collect(),
+            self.wan_address_changes.collect(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::series;
+
+    fn set(json: &str) -> WanMetrics {
+        let router_status = List::from_reader(json.as_bytes(), WanMetrics::SUBTREES).unwrap();
+        let wan_metrics = WanMetrics::new().unwrap();
+        wan_metrics.set(&router_status).unwrap();
+        wan_metrics
+    }
+
+    #[test]
+    fn router_mode() {
+        // A gateway isn't always set, which the hub sends as an empty address
+        let wan_metrics = set(r#"{
+            "1.3.6.1.4.1.4115.1.20.1.1.1.7.1.1.3.1": "$51020304",
+            "1.3.6.1.4.1.4115.1.20.1.1.1.7.1.1.5.1": "$",
+            "1.3.6.1.4.1.4115.1.20.1.1.1.11.2.1.2.1": "$01010101"
+        }"#);
+
+        assert_eq!(
+            series(&wan_metrics.hub_mode),
+            [
+                ("mode=modem".to_owned(), 0.0),
+                ("mode=router".to_owned(), 1.0)
+            ]
+            .into()
+        );
+        assert_eq!(
+            series(&wan_metrics.wan_info),
+            [(
+                "dns_servers=1.1.1.1,gateway=,ipv4=81.2.3.4,ipv6=,lease_time=".to_owned(),
+                1.0
+            )]
+            .into()
+        );
+    }
+
+    #[test]
+    fn modem_mode() {
+        let wan_metrics = set(r#"{
+            "1.3.6.1.4.1.4115.1.20.1.1.1.7.1.1.3.1": "$00000000"
+        }"#);
+
+        assert_eq!(
+            series(&wan_metrics.hub_mode),
+            [
+                ("mode=modem".to_owned(), 1.0),
+                ("mode=router".to_owned(), 0.0)
+            ]
+            .into()
+        );
+        assert!(series(&wan_metrics.wan_info).is_empty());
+    }
+
+    #[test]
+    fn unparseable_rows_leave_the_mode_unknown() {
+        let wan_metrics = set(r#"{
+            "1.3.6.1.4.1.4115.1.20.1.1.1.7.1.1.3.1": "$510203"
+        }"#);
+
+        assert!(series(&wan_metrics.hub_mode).is_empty());
+        assert!(series(&wan_metrics.wan_info).is_empty());
+    }
+}
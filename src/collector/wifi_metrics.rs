@@ -0,0 +1,318 @@
+use super::{reset_rows, ClientPrivacy, UIntGaugeVec};
+use crate::snmp::{oid, EnumValue, Enumeration, List, MacAddress, Table, TableRow, OID};
+
+use anyhow::Result;
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    IntGaugeVec, Opts,
+};
+use std::rc::Rc;
+
+const ARRIS_ROUTER_WI_FI_RADIO_TABLE: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.3.22"); // arrisRouterWiFiRadioTable
+const ARRIS_ROUTER_WI_FI_RADIO_ENABLE: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.3.22.1.1"); // arrisRouterWiFiRadioEnable
+const ARRIS_ROUTER_WI_FI_RADIO_BAND: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.3.22.1.2"); // arrisRouterWiFiRadioBand
+const ARRIS_ROUTER_WI_FI_RADIO_CHANNEL: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.3.22.1.3"); // arrisRouterWiFiRadioChannel
+const ARRIS_ROUTER_WI_FI_RADIO_CHANNEL_BANDWIDTH: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.3.22.1.4"); // arrisRouterWiFiRadioChannelBandwidth
+
+#[derive(Clone, Copy, Debug, Enumeration)]
+#[snmp(name = "arrisRouterWiFiRadioBand")]
+enum WiFiBand {
+    #[snmp(label = "2.4ghz")]
+    Band2GHz = 1,
+    #[snmp(label = "5ghz")]
+    Band5GHz = 2,
+}
+
+#[derive(Clone, Copy, Debug, Enumeration)]
+#[snmp(name = "arrisRouterWiFiRadioChannelBandwidth")]
+enum WiFiChannelBandwidth {
+    #[snmp(label = "20mhz")]
+    MHz20 = 1,
+    #[snmp(label = "40mhz")]
+    MHz40 = 2,
+    #[snmp(label = "80mhz")]
+    MHz80 = 3,
+    #[snmp(label = "160mhz")]
+    MHz160 = 4,
+}
+
+impl WiFiChannelBandwidth {
+    fn hertz(self) -> u64 {
+        use WiFiChannelBandwidth::*;
+
+        match self {
+            MHz20 => 20_000_000,
+            MHz40 => 40_000_000,
+            MHz80 => 80_000_000,
+            MHz160 => 160_000_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, TableRow)]
+struct WiFiRadio {
+    #[snmp(column = ARRIS_ROUTER_WI_FI_RADIO_ENABLE, kind = truth_value)]
+    enable: bool,
+    #[snmp(column = ARRIS_ROUTER_WI_FI_RADIO_BAND)]
+    band: EnumValue<WiFiBand>,
+    #[snmp(column = ARRIS_ROUTER_WI_FI_RADIO_CHANNEL)]
+    channel: u64,
+    #[snmp(column = ARRIS_ROUTER_WI_FI_RADIO_CHANNEL_BANDWIDTH)]
+    channel_bandwidth: Option<EnumValue<WiFiChannelBandwidth>>,
+}
+
+const ARRIS_ROUTER_WI_FI_CLIENT_INFO_TABLE: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.3.42"); // arrisRouterWiFiClientInfoTable
+const ARRIS_ROUTER_WI_FI_CLIENT_INFO_MAC: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.3.42.1.2"); // arrisRouterWiFiClientInfoMAC
+const ARRIS_ROUTER_WI_FI_CLIENT_INFO_RSSI: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.3.42.1.4"); // arrisRouterWiFiClientInfoRSSI
+const ARRIS_ROUTER_WI_FI_CLIENT_INFO_TX_RATE: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.3.42.1.7"); // arrisRouterWiFiClientInfoTxRate
+const ARRIS_ROUTER_WI_FI_CLIENT_INFO_RX_RATE: OID = oid!("1.3.6.1.4.1.4115.1.20.1.1.3.42.1.8"); // arrisRouterWiFiClientInfoRxRate
+
+#[derive(Clone, Debug, TableRow)]
+struct WiFiClient {
+    #[snmp(column = ARRIS_ROUTER_WI_FI_CLIENT_INFO_MAC, kind = mac_address)]
+    mac: MacAddress,
+    #[snmp(column = ARRIS_ROUTER_WI_FI_CLIENT_INFO_RSSI, kind = integer)]
+    rssi: Option<i64>,
+    #[snmp(column = ARRIS_ROUTER_WI_FI_CLIENT_INFO_TX_RATE, kind = gauge32)]
+    tx_rate: Option<u64>,
+    #[snmp(column = ARRIS_ROUTER_WI_FI_CLIENT_INFO_RX_RATE, kind = gauge32)]
+    rx_rate: Option<u64>,
+}
+
+pub struct WiFiMetrics {
+    privacy: ClientPrivacy,
+
+    wifi_radio_enabled: UIntGaugeVec,
+    wifi_radio_channel: UIntGaugeVec,
+    wifi_radio_channel_width: UIntGaugeVec,
+    wifi_radio_associated_clients: UIntGaugeVec,
+    wifi_client_rssi: IntGaugeVec,
+    wifi_client_tx_rate: UIntGaugeVec,
+    wifi_client_rx_rate: UIntGaugeVec,
+}
+
+impl WiFiMetrics {
+    pub const SUBTREES: &'static [OID] = &[
+        ARRIS_ROUTER_WI_FI_RADIO_TABLE,
+        ARRIS_ROUTER_WI_FI_CLIENT_INFO_TABLE,
+    ];
+
+    pub fn new(privacy: ClientPrivacy) -> Result<Self> {
+        Ok(WiFiMetrics {
+            privacy,
+
+            wifi_radio_enabled: UIntGaugeVec::new(
+                Opts::new("wifi_radio_enabled", "Wi-Fi Radio Enabled"),
+                &["radio", "band"],
+            )?,
+            wifi_radio_channel: UIntGaugeVec::new(
+                Opts::new("wifi_radio_channel", "Wi-Fi Radio Channel"),
+                &["radio", "band"],
+            )?,
+            wifi_radio_channel_width: UIntGaugeVec::new(
+                Opts::new("wifi_radio_channel_width", "Wi-Fi Radio Channel Width (Hz)"),
+                &["radio", "band"],
+            )?,
+            wifi_radio_associated_clients: UIntGaugeVec::new(
+                Opts::new(
+                    "wifi_radio_associated_clients",
+                    "Wi-Fi Radio Associated Clients",
+                ),
+                &["radio", "band"],
+            )?,
+            wifi_client_rssi: IntGaugeVec::new(
+                Opts::new("wifi_client_rssi", "Wi-Fi Client RSSI (dBm)"),
+                &["radio", "band", "mac"],
+            )?,
+            wifi_client_tx_rate: UIntGaugeVec::new(
+                Opts::new("wifi_client_tx_rate", "Wi-Fi Client Transmit Rate (kbps)"),
+                &["radio", "band", "mac"],
+            )?,
+            wifi_client_rx_rate: UIntGaugeVec::new(
+                Opts::new("wifi_client_rx_rate", "Wi-Fi Client Receive Rate (kbps)"),
+                &["radio", "band", "mac"],
+            )?,
+        })
+    }
+
+    pub fn set(&self, router_status: &List) -> Result<()> {
+        // Both tables are empty when the hub is in modem mode, and not every firmware has the
+        // client table
+        let wifi_radio_table: Rc<Table<WiFiRadio, u32>> =
+            router_status.parse_table(&ARRIS_ROUTER_WI_FI_RADIO_TABLE)?;
+        let wifi_client_table: Rc<Table<WiFiClient, (u32, String)>> =
+            router_status.parse_table(&ARRIS_ROUTER_WI_FI_CLIENT_INFO_TABLE)?;
+
+        self.reset();
+
+        // Without their MAC addresses every client on a radio would share the same labels
+        if self.privacy.identifies_clients() {
+            self.set_clients(&wifi_radio_table, &wifi_client_table);
+        }
+
+        for (index, wifi_radio) in wifi_radio_table.iter() {
+            let labels = [&index.to_string(), &*wifi_radio.band.label()];
+
+            self.wifi_radio_enabled
+                .with_label_values(&labels)
+                .set(wifi_radio.enable as u64);
+
+            self.wifi_radio_channel
+                .with_label_values(&labels)
+                .set(wifi_radio.channel);
+
+            if let Some(channel_bandwidth) = wifi_radio.channel_bandwidth.and_then(EnumValue::known)
+            {
+                self.wifi_radio_channel_width
+                    .with_label_values(&labels)
+                    .set(channel_bandwidth.hertz());
+            }
+
+            // Firmware without the client table can't be told apart from a hub with no clients,
+            // so there's no count rather than a misleading 0
+            if !wifi_client_table.is_empty() {
+                self.wifi_radio_associated_clients
+                    .with_label_values(&labels)
+                    .set(wifi_client_table.with_prefix(index).count() as u64);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_clients(
+        &self,
+        wifi_radio_table: &Table<WiFiRadio, u32>,
+        wifi_client_table: &Table<WiFiClient, (u32, String)>,
+    ) {
+        for ((radio, _), wifi_client) in wifi_client_table.iter() {
+            let band = match wifi_radio_table.get(radio) {
+                Some(wifi_radio) => wifi_radio.band.label(),
+                None => "".into(),
+            };
+            let labels = [
+                &radio.to_string(),
+                &*band,
+                &self.privacy.apply(&wifi_client.mac.to_string()),
+            ];
+
+            if let Some(rssi) = wifi_client.rssi {
+                self.wifi_client_rssi.with_label_values(&labels).set(rssi);
+            }
+            if let Some(tx_rate) = wifi_client.tx_rate {
+                self.wifi_client_tx_rate
+                    .with_label_values(&labels)
+                    .set(tx_rate);
+            }
+            if let Some(rx_rate) = wifi_client.rx_rate {
+                self.wifi_client_rx_rate
+                    .with_label_values(&labels)
+                    .set(rx_rate);
+            }
+        }
+    }
+
+    fn reset(&self) {
+        reset_rows(&[
+            &self.wifi_radio_enabled,
+            &self.wifi_radio_channel,
+            &self.wifi_radio_channel_width,
+            &self.wifi_radio_associated_clients,
+            &self.wifi_client_rssi,
+            &self.wifi_client_tx_rate,
+            &self.wifi_client_rx_rate,
+        ]);
+    }
+}
+
+impl Collector for WiFiMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![
+            self.wifi_radio_enabled.desc(),
+            self.wifi_radio_channel.desc(),
+            self.wifi_radio_channel_width.desc(),
+            self.wifi_radio_associated_clients.desc(),
+            self.wifi_client_rssi.desc(),
+            self.wifi_client_tx_rate.desc(),
+            self.wifi_client_rx_rate.desc(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        vec![
+            self.wifi_radio_enabled.collect(),
+            self.wifi_radio_channel.collect(),
+            self.wifi_radio_channel_width.collect(),
+            self.wifi_radio_associated_clients.collect(),
+            self.wifi_client_rssi.collect(),
+            self.wifi_client_tx_rate.collect(),
+            self.wifi_client_rx_rate.collect(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::{series, LanClientPrivacy};
+
+    const ROUTER_STATUS: &str = r#"{
+        "1.3.6.1.4.1.4115.1.20.1.1.3.22.1.1.10001": "1",
+        "1.3.6.1.4.1.4115.1.20.1.1.3.22.1.2.10001": "1",
+        "1.3.6.1.4.1.4115.1.20.1.1.3.22.1.3.10001": "6",
+        "1.3.6.1.4.1.4115.1.20.1.1.3.42.1.2.10001.1": "$001122334455",
+        "1.3.6.1.4.1.4115.1.20.1.1.3.42.1.4.10001.1": "-50",
+        "1.3.6.1.4.1.4115.1.20.1.1.3.42.1.2.10001.2": "$001122334466",
+        "1.3.6.1.4.1.4115.1.20.1.1.3.42.1.4.10001.2": "-70"
+    }"#;
+
+    fn set(privacy: LanClientPrivacy) -> WiFiMetrics {
+        let router_status =
+            List::from_reader(ROUTER_STATUS.as_bytes(), WiFiMetrics::SUBTREES).unwrap();
+        let wifi_metrics = WiFiMetrics::new(ClientPrivacy::new(privacy, Some("key"))).unwrap();
+        wifi_metrics.set(&router_status).unwrap();
+        wifi_metrics
+    }
+
+    #[test]
+    fn clients() {
+        let wifi_metrics = set(LanClientPrivacy::None);
+
+        assert_eq!(
+            series(&wifi_metrics.wifi_client_rssi),
+            [
+                (
+                    "band=2.4ghz,mac=00:11:22:33:44:55,radio=10001".to_owned(),
+                    -50.0
+                ),
+                (
+                    "band=2.4ghz,mac=00:11:22:33:44:66,radio=10001".to_owned(),
+                    -70.0
+                ),
+            ]
+            .into()
+        );
+        assert_eq!(
+            series(&wifi_metrics.wifi_radio_associated_clients),
+            [("band=2.4ghz,radio=10001".to_owned(), 2.0)].into()
+        );
+    }
+
+    #[test]
+    fn dropped_clients_are_only_counted() {
+        let wifi_metrics = set(LanClientPrivacy::Drop);
+
+        assert!(series(&wifi_metrics.wifi_client_rssi).is_empty());
+        assert_eq!(
+            series(&wifi_metrics.wifi_radio_associated_clients),
+            [("band=2.4ghz,radio=10001".to_owned(), 2.0)].into()
+        );
+    }
+}
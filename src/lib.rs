@@ -0,0 +1,6 @@
+pub mod app;
+pub mod client;
+pub mod collector;
+mod routes;
+pub mod settings;
+pub mod snmp;
@@ -1,12 +1,5 @@
-mod app;
-mod client;
-mod collector;
-mod routes;
-mod settings;
-mod snmp;
-
-use app::Application;
 use env_logger::Env;
+use virgin_media_prometheus_exporter::{app::Application, settings};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -0,0 +1,13 @@
+use crate::client::VirginHubClient;
+use crate::collector::fetch_events;
+
+use actix_web::{web::Data, HttpResponse};
+
+pub async fn events(client: Data<VirginHubClient>) -> Result<HttpResponse, HttpResponse> {
+    let events = fetch_events(&client).map_err(|e| {
+        log::error!("error fetching events: {:?}", e);
+        HttpResponse::BadGateway()
+    })?;
+
+    Ok(HttpResponse::Ok().json(events))
+}
@@ -1,5 +1,7 @@
+mod events;
 mod health_check;
 mod metrics;
 
+pub use events::*;
 pub use health_check::*;
 pub use metrics::*;
@@ -1,3 +1,5 @@
+use crate::collector::{ChannelLabels, LanClientPrivacy};
+
 use anyhow::Result;
 use config::{Config, File};
 use serde_aux::field_attributes::deserialize_number_from_string;
@@ -8,6 +10,23 @@ pub struct Settings {
     pub hub_ip: IpAddr,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
+    #[serde(default)]
+    pub channel_labels: ChannelLabels,
+    #[serde(default)]
+    pub lan_client_privacy: LanClientPrivacy,
+    #[serde(default)]
+    pub lan_client_hash_key: Option<String>,
+    #[serde(default)]
+    pub mta: bool,
+    #[serde(
+        default = "default_max_response_size",
+        deserialize_with = "deserialize_number_from_string"
+    )]
+    pub max_response_size: u64,
+}
+
+fn default_max_response_size() -> u64 {
+    8 * 1024 * 1024
 }
 
 pub fn load_settings() -> Result<Settings> {
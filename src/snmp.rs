@@ -1,45 +1,126 @@
-use anyhow::{anyhow, Context, Error, Result};
+mod index;
+mod oid;
+mod value;
+
+pub use index::*;
+pub use oid::*;
+pub use snmp_derive::{Enumeration, TableRow};
+pub use value::*;
+
+use anyhow::{bail, Context, Error, Result};
+use serde::{
+    de::{IgnoredAny, MapAccess, Visitor},
+    Deserializer,
+};
 use std::{
-    borrow::Cow,
-    collections::{hash_map::Iter, HashMap},
+    any::{Any, TypeId},
+    borrow::Borrow,
+    cell::RefCell,
+    collections::{hash_map::Iter, BTreeMap, HashMap},
     convert::TryFrom,
-    fmt::{Debug, Display},
+    fmt::{self, Debug, Display},
+    hash::Hash,
+    io::Read,
+    ops::Bound,
+    rc::Rc,
     str::FromStr,
+    time::Duration,
 };
 
-const SCALAR_SUFFIX: &'static str = ".0";
-const TABLE_ENTRY_SUFFIX: &'static str = ".1";
+const SCALAR_ARC: u32 = 0;
+const TABLE_ENTRY_ARC: u32 = 1;
+
+/// A row that couldn't be parsed, so was left out of its table.
+#[derive(Clone, Debug)]
+pub struct RowError {
+    pub table: OID,
+    pub index: String,
+    /// The column that failed, if the error came from reading one.
+    pub column: Option<OID>,
+    pub message: String,
+}
+
+/// A snapshot of the hub's values, ordered by OID so that a table can be read without scanning
+/// every value. Parsed tables are cached for the life of the snapshot, as
+/// some are read by more than one collector.
+#[derive(Debug, Default)]
+pub struct List {
+    values: BTreeMap<OID, String>,
+    parsed_tables: RefCell<HashMap<(OID, TypeId), Box<dyn Any>>>,
+    row_errors: RefCell<Vec<RowError>>,
+}
+
+/// Collects a response's values, skipping those outside `subtrees` without allocating them.
+struct ValuesVisitor<'a> {
+    subtrees: &'a [OID],
+}
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, serde::Deserialize)]
-pub struct OID(Cow<'static, str>);
+impl<'de> Visitor<'de> for ValuesVisitor<'_> {
+    type Value = BTreeMap<OID, String>;
 
-impl OID {
-    pub const fn new(value: &'static str) -> Self {
-        Self(Cow::Borrowed(value))
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a map of OIDs to values")
     }
 
-    fn trim_suffix(self, s: &str) -> OID {
-        if self.0.as_ref().ends_with(s) {
-            OID(Cow::Owned(
-                self.0.as_ref()[..self.0.as_ref().len() - s.len()].to_owned(),
-            ))
-        } else {
-            self
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut values = BTreeMap::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            let oid = match key.parse::<OID>() {
+                Ok(oid) => oid,
+                Err(e) => {
+                    log::debug!("ignoring value: {}", e);
+                    map.next_value::<IgnoredAny>()?;
+                    continue;
+                }
+            };
+
+            let wanted = self
+                .subtrees
+                .iter()
+                .any(|subtree| oid.index_after(subtree).is_some());
+            if wanted {
+                values.insert(oid, map.next_value()?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
         }
+
+        Ok(values)
     }
 }
 
-#[derive(Clone, Debug, serde::Deserialize)]
-pub struct List(HashMap<OID, String>);
+/// An error reading a column of a table row.
+#[derive(Debug)]
+pub struct ColumnError {
+    pub column: OID,
+    pub message: String,
+}
 
+impl Display for ColumnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for ColumnError {}
+
+/// A table row, keyed by column number, which is the last arc of the column's OID.
 #[derive(Clone, Debug)]
-pub struct TableEntry(HashMap<OID, String>);
+pub struct TableEntry(HashMap<u32, String>);
 
 impl TableEntry {
+    fn get(&self, oid: &OID) -> Option<&String> {
+        self.0.get(oid.arcs().last()?)
+    }
+
     pub fn get_column(&self, oid: &OID) -> Result<&String> {
-        self.0
-            .get(oid)
-            .context(format!("column not found: {}", oid.0))
+        self.get(oid).ok_or_else(|| {
+            Error::new(ColumnError {
+                column: oid.clone(),
+                message: "not found".to_owned(),
+            })
+        })
     }
 
     pub fn parse_column<T>(&self, oid: &OID) -> Result<T>
@@ -47,30 +128,176 @@ impl TableEntry {
         T: FromStr,
         <T as FromStr>::Err: Debug,
     {
-        self.get_column(oid)
-            .and_then(|s| T::from_str(s).map_err(|e| anyhow!("{:?}", e)))
+        self.get_column(oid).and_then(|s| Self::parse_value(oid, s))
+    }
+
+    pub fn parse_optional_column<T>(&self, oid: &OID) -> Result<Option<T>>
+    where
+        T: FromStr,
+        <T as FromStr>::Err: Debug,
+    {
+        self.get(oid).map(|s| Self::parse_value(oid, s)).transpose()
+    }
+
+    fn parse_value<T>(oid: &OID, s: &str) -> Result<T>
+    where
+        T: FromStr,
+        <T as FromStr>::Err: Debug,
+    {
+        T::from_str(s).map_err(|e| {
+            Error::new(ColumnError {
+                column: oid.clone(),
+                message: format!("{:?}", e),
+            })
+        })
     }
 }
 
+/// Generates a `parse_<type>` and `parse_optional_<type>` column accessor for each SMI type.
+macro_rules! column_accessors {
+    ($($name:ident, $optional_name:ident: $value:ty => $output:ty;)*) => {
+        // Not every type appears in a table the collectors read yet
+        #[allow(dead_code)]
+        impl TableEntry {
+            $(
+                pub fn $name(&self, oid: &OID) -> Result<$output> {
+                    self.parse_column::<$value>(oid).map(Into::into)
+                }
+
+                pub fn $optional_name(&self, oid: &OID) -> Result<Option<$output>> {
+                    Ok(self.parse_optional_column::<$value>(oid)?.map(Into::into))
+                }
+            )*
+        }
+    };
+}
+
+column_accessors! {
+    parse_integer, parse_optional_integer: Integer => i64;
+    parse_truth_value, parse_optional_truth_value: TruthValue => bool;
+    parse_counter32, parse_optional_counter32: Counter32 => u64;
+    parse_counter64, parse_optional_counter64: Counter64 => u64;
+    parse_gauge32, parse_optional_gauge32: Gauge32 => u64;
+    parse_time_ticks, parse_optional_time_ticks: TimeTicks => Duration;
+    parse_octet_string, parse_optional_octet_string: OctetString => OctetString;
+    parse_mac_address, parse_optional_mac_address: MacAddress => MacAddress;
+    parse_inet_address, parse_optional_inet_address: InetAddress => InetAddress;
+    parse_date_and_time, parse_optional_date_and_time: DateAndTime => DateAndTime;
+}
+
+/// The rows of a table, keyed by their index, which is the raw dotted `String` unless the table is
+/// parsed with a typed [`Index`].
 #[derive(Clone, Debug)]
-pub struct Table<T = TableEntry>(HashMap<String, T>);
+pub struct Table<T = TableEntry, I = String>(HashMap<I, T>);
 
-impl<T> Table<T> {
-    pub fn get(&self, index: &str) -> Option<&T> {
+impl<T, I: Eq + Hash> Table<T, I> {
+    pub fn get<Q>(&self, index: &Q) -> Option<&T>
+    where
+        I: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
         self.0.get(index)
     }
 
-    pub fn iter(&self) -> Iter<'_, String, T> {
+    pub fn iter(&self) -> Iter<'_, I, T> {
         self.0.iter()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The rows by reference, so a cached table can be joined without cloning its rows.
+    pub fn by_ref(&self) -> Table<&T, I>
+    where
+        I: Clone,
+    {
+        Table(
+            self.0
+                .iter()
+                .map(|(index, row)| (index.clone(), row))
+                .collect(),
+        )
+    }
+
+    /// The rows whose index starts with `prefix`.
+    pub fn with_prefix<'a, P>(&'a self, prefix: &'a P) -> impl Iterator<Item = (&'a I, &'a T)>
+    where
+        I: IndexPrefix<P>,
+        P: PartialEq,
+    {
+        self.0
+            .iter()
+            .filter(move |(index, _)| index.prefix() == prefix)
+    }
+
+    /// Pairs each row with the row at the same index in `other`, dropping rows `other` lacks.
+    pub fn join<U>(self, other: &Table<U, I>) -> Table<(T, &U), I> {
+        Table(
+            self.0
+                .into_iter()
+                .filter_map(|(index, row)| {
+                    let other_row = other.get(&index)?;
+                    Some((index, (row, other_row)))
+                })
+                .collect(),
+        )
+    }
+
+    /// Pairs each row with the row at the same index in `other`, if it has one. Joins can be
+    /// chained, e.g. `a.left_join(&b).left_join(&c)` has rows of `((a, b), c)`.
+    pub fn left_join<U>(self, other: &Table<U, I>) -> Table<(T, Option<&U>), I> {
+        Table(
+            self.0
+                .into_iter()
+                .map(|(index, row)| {
+                    let other_row = other.get(&index);
+                    (index, (row, other_row))
+                })
+                .collect(),
+        )
+    }
 }
 
 impl List {
+    /// Decodes a response as it's read, keeping only the values under `subtrees`, so a large
+    /// response is never held in memory whole.
+    pub fn from_reader<R: Read>(reader: R, subtrees: &[OID]) -> Result<Self> {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let values = deserializer.deserialize_map(ValuesVisitor { subtrees })?;
+        deserializer.end()?;
+
+        Ok(List {
+            values,
+            ..List::default()
+        })
+    }
+
+    /// Takes the rows that `parse_table` has skipped since this was last called.
+    pub fn take_row_errors(&self) -> Vec<RowError> {
+        self.row_errors.take()
+    }
+
+    /// Whether `parse_table` has left out any rows of `table` that haven't been taken yet.
+    pub fn has_row_errors(&self, table: &OID) -> bool {
+        self.row_errors
+            .borrow()
+            .iter()
+            .any(|row_error| row_error.table == *table)
+    }
+
+    /// The values in the subtree under `prefix`, in order, with the arcs that follow it.
+    fn subtree(&self, prefix: OID) -> impl Iterator<Item = (&[u32], &String)> {
+        self.values
+            .range((Bound::Included(&prefix), Bound::Unbounded))
+            .map_while(move |(oid, value)| Some((oid.index_after(&prefix)?, value)))
+    }
+
     pub fn get_scalar(&self, oid: &OID) -> Result<&String> {
-        let oid = OID(Cow::Owned(format!("{}{}", oid.0, SCALAR_SUFFIX)));
-        self.0
-            .get(&oid)
-            .context(format!("scalar not found: {}", oid.0))
+        let scalar = oid.child(SCALAR_ARC);
+        self.values
+            .get(&scalar)
+            .context(format!("scalar not found: {}", scalar))
     }
 
     pub fn parse_scalar<T>(&self, oid: &OID) -> Result<T>
@@ -82,49 +309,258 @@ impl List {
             .and_then(|s| T::from_str(s).map_err(Error::msg))
     }
 
-    pub fn get_table(&self, oid: &OID) -> Result<Table> {
-        let mut table: HashMap<String, TableEntry> = HashMap::new();
-        let table_entry_prefix = format!("{}{}.", oid.0, TABLE_ENTRY_SUFFIX);
+    pub fn parse_optional_scalar<T>(&self, oid: &OID) -> Result<Option<T>>
+    where
+        T: FromStr,
+        <T as FromStr>::Err: Debug + Display + Send + Sync + 'static,
+    {
+        self.values
+            .get(&oid.child(SCALAR_ARC))
+            .map(|s| T::from_str(s).map_err(Error::msg))
+            .transpose()
+    }
 
-        for (oid, value) in self
-            .0
-            .iter()
-            .filter(|(oid, _)| oid.0.starts_with(&table_entry_prefix))
-        {
-            let index = oid
-                .0
-                .trim_start_matches(&table_entry_prefix)
-                .splitn(2, ".")
-                .nth(1)
-                .context("failed to extract table index")?
-                .to_owned();
-
-            let column = oid.clone().trim_suffix(&format!(".{}", index));
-
-            let entry = table.entry(index).or_insert(TableEntry(HashMap::new()));
+    /// The rows of a table, keyed by the arcs of their index.
+    fn table_rows(&self, oid: &OID) -> Result<HashMap<&[u32], TableEntry>> {
+        let mut rows: HashMap<&[u32], TableEntry> = HashMap::new();
+
+        for (arcs, value) in self.subtree(oid.child(TABLE_ENTRY_ARC)) {
+            let (column, index) = match arcs.split_first() {
+                Some((column, index)) if !index.is_empty() => (*column, index),
+                _ => bail!("failed to extract table index"),
+            };
+
+            let entry = rows.entry(index).or_insert(TableEntry(HashMap::new()));
             entry.0.insert(column, value.clone());
         }
 
-        Ok(Table(table))
+        Ok(rows)
     }
 
-    pub fn parse_table<T>(&self, oid: &OID) -> Result<Table<T>>
+    /// Parses each row of a table, leaving out rows that fail to parse rather than failing the
+    /// whole table. The rows left out can be taken with `take_row_errors`.
+    ///
+    /// The result is cached, so reading the table again as the same type doesn't parse it again.
+    pub fn parse_table<T, I>(&self, oid: &OID) -> Result<Rc<Table<T, I>>>
     where
-        T: TryFrom<TableEntry>,
-        <T as TryFrom<TableEntry>>::Error: Debug + Display + Send + Sync + 'static,
+        T: TryFrom<TableEntry> + 'static,
+        <T as TryFrom<TableEntry>>::Error: Into<Error>,
+        I: Index + Eq + Hash + 'static,
     {
-        self.get_table(oid)
-            .and_then(|table| {
-                table
-                    .0
-                    .into_iter()
-                    .map(|(index, entry)| {
-                        T::try_from(entry)
-                            .map(|value| (index, value))
-                            .map_err(Error::msg)
-                    })
-                    .collect::<Result<HashMap<_, _>>>()
-            })
-            .map(Table)
+        let key = (oid.clone(), TypeId::of::<Table<T, I>>());
+        if let Some(table) = self.parsed_tables.borrow().get(&key) {
+            if let Some(table) = table.downcast_ref::<Rc<Table<T, I>>>() {
+                return Ok(Rc::clone(table));
+            }
+        }
+
+        let mut rows = HashMap::new();
+
+        for (index, entry) in self.table_rows(oid)? {
+            let row = T::try_from(entry)
+                .map_err(Into::into)
+                .and_then(|row| Ok((I::from_arcs(index)?, row)));
+
+            match row {
+                Ok((index, row)) => {
+                    rows.insert(index, row);
+                }
+                Err(e) => self.row_errors.borrow_mut().push(RowError {
+                    table: oid.clone(),
+                    index: dotted(index),
+                    column: e
+                        .downcast_ref::<ColumnError>()
+                        .map(|column_error| column_error.column.clone()),
+                    message: format!("{:#}", e),
+                }),
+            }
+        }
+
+        let table = Rc::new(Table(rows));
+        self.parsed_tables
+            .borrow_mut()
+            .insert(key, Box::new(Rc::clone(&table)));
+
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_TABLE: OID = oid!("1.3.6.1.4.1.99.1");
+    const TEST_STATE: OID = oid!("1.3.6.1.4.1.99.1.1.1");
+    const TEST_POWER: OID = oid!("1.3.6.1.4.1.99.1.1.2");
+    const TEST_COUNT: OID = oid!("1.3.6.1.4.1.99.1.1.3");
+    const TEST_NAME: OID = oid!("1.3.6.1.4.1.99.1.1.4");
+
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Enumeration)]
+    #[snmp(name = "testState")]
+    enum TestState {
+        AuthRejectWait = 1,
+        QAM256 = 2,
+        DhcpV4InProgress = 3,
+        #[snmp(label = "2.4ghz")]
+        Band2GHz = 4,
+    }
+
+    #[derive(Clone, Debug, TableRow)]
+    struct TestRow {
+        #[snmp(column = TEST_STATE)]
+        state: EnumValue<TestState>,
+        #[snmp(column = TEST_POWER, kind = integer, scale = 10)]
+        power: f64,
+        #[snmp(column = TEST_COUNT, kind = counter32)]
+        count: Option<u64>,
+        #[snmp(column = TEST_NAME)]
+        name: String,
+    }
+
+    fn list(json: &str) -> List {
+        List::from_reader(json.as_bytes(), &[TEST_TABLE]).unwrap()
+    }
+
+    #[test]
+    fn enumeration_labels() {
+        assert_eq!(TestState::NAME, "testState");
+        assert_eq!(
+            TestState::LABELS,
+            &["auth_reject_wait", "qam256", "dhcpv4_in_progress", "2.4ghz"]
+        );
+        assert_eq!(TestState::QAM256.label(), "qam256");
+    }
+
+    #[test]
+    fn enumeration_codes() {
+        assert_eq!(TestState::from_code(3), Some(TestState::DhcpV4InProgress));
+        assert_eq!(TestState::from_code(5), None);
+        assert_eq!(TestState::Band2GHz.code(), 4);
+    }
+
+    #[test]
+    fn enum_value_unknown_code() {
+        let known: EnumValue<TestState> = "1".parse().unwrap();
+        assert_eq!(known.known(), Some(TestState::AuthRejectWait));
+        assert_eq!(known.label(), "auth_reject_wait");
+
+        let unknown: EnumValue<TestState> = "9".parse().unwrap();
+        assert_eq!(unknown.known(), None);
+        assert_eq!(unknown.code(), 9);
+        assert_eq!(unknown.label(), "9");
+    }
+
+    #[test]
+    fn table_row_columns() {
+        let list = list(
+            r#"{
+                "1.3.6.1.4.1.99.1.1.1.7": "2",
+                "1.3.6.1.4.1.99.1.1.2.7": "-35",
+                "1.3.6.1.4.1.99.1.1.3.7": "12",
+                "1.3.6.1.4.1.99.1.1.4.7": "$6e6173"
+            }"#,
+        );
+
+        let table: Rc<Table<TestRow, u32>> = list.parse_table(&TEST_TABLE).unwrap();
+        let row = table.get(&7).unwrap();
+        assert_eq!(row.state.known(), Some(TestState::QAM256));
+        assert_eq!(row.power, -3.5);
+        assert_eq!(row.count, Some(12));
+        assert_eq!(row.name, "$6e6173");
+    }
+
+    #[test]
+    fn table_row_optional_column() {
+        let list = list(
+            r#"{
+                "1.3.6.1.4.1.99.1.1.1.7": "1",
+                "1.3.6.1.4.1.99.1.1.2.7": "0",
+                "1.3.6.1.4.1.99.1.1.4.7": "nas"
+            }"#,
+        );
+
+        let table: Rc<Table<TestRow, u32>> = list.parse_table(&TEST_TABLE).unwrap();
+        assert_eq!(table.get(&7).unwrap().count, None);
+        assert!(list.take_row_errors().is_empty());
+    }
+
+    #[test]
+    fn table_row_errors() {
+        let list = list(
+            r#"{
+                "1.3.6.1.4.1.99.1.1.1.7": "1",
+                "1.3.6.1.4.1.99.1.1.2.7": "0",
+                "1.3.6.1.4.1.99.1.1.4.7": "nas",
+                "1.3.6.1.4.1.99.1.1.1.8": "1",
+                "1.3.6.1.4.1.99.1.1.2.8": "high",
+                "1.3.6.1.4.1.99.1.1.4.8": "laptop",
+                "1.3.6.1.4.1.99.1.1.1.9": "1",
+                "1.3.6.1.4.1.99.1.1.2.9": "0"
+            }"#,
+        );
+
+        let table: Rc<Table<TestRow, u32>> = list.parse_table(&TEST_TABLE).unwrap();
+        assert!(table.get(&7).is_some());
+        assert!(table.get(&8).is_none());
+        assert!(table.get(&9).is_none());
+
+        let mut row_errors = list.take_row_errors();
+        row_errors.sort_by(|a, b| a.index.cmp(&b.index));
+        let columns = row_errors
+            .iter()
+            .map(|row_error| (row_error.index.as_str(), row_error.column.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(columns, [("8", Some(TEST_POWER)), ("9", Some(TEST_NAME))]);
+    }
+
+    #[test]
+    fn joins() {
+        let a = Table(HashMap::from([(1, "a1"), (2, "a2")]));
+        let b = Table(HashMap::from([(1, "b1"), (2, "b2")]));
+        let c = Table(HashMap::from([(1, "c1")]));
+
+        let left_joined = a.by_ref().left_join(&b).left_join(&c);
+        assert_eq!(
+            left_joined.get(&1),
+            Some(&((&"a1", Some(&"b1")), Some(&"c1")))
+        );
+        assert_eq!(left_joined.get(&2), Some(&((&"a2", Some(&"b2")), None)));
+
+        let joined = a.by_ref().join(&c);
+        assert_eq!(joined.get(&1), Some(&(&"a1", &"c1")));
+        assert_eq!(joined.get(&2), None);
+    }
+
+    #[test]
+    fn values_outside_subtrees_are_dropped() {
+        let list = List::from_reader(
+            r#"{
+                "1.3.6.1.4.1.99.1.1.1.7": "1",
+                "1.3.6.1.4.1.99.10.1.1.7": "1",
+                "1.3.6.1.4.1.98.1": "1",
+                "not an oid": "1"
+            }"#
+            .as_bytes(),
+            &[TEST_TABLE],
+        )
+        .unwrap();
+
+        assert_eq!(
+            list.values.keys().collect::<Vec<_>>(),
+            [&oid!("1.3.6.1.4.1.99.1.1.1.7")]
+        );
+    }
+
+    #[test]
+    fn parse_table_is_cached() {
+        let list = list(r#"{"1.3.6.1.4.1.99.1.1.1.7": "1"}"#);
+
+        let first: Rc<Table<TableEntry, u32>> = list.parse_table(&TEST_TABLE).unwrap();
+        let second: Rc<Table<TableEntry, u32>> = list.parse_table(&TEST_TABLE).unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+
+        // Another row type or index is parsed separately
+        let raw: Rc<Table<TableEntry>> = list.parse_table(&TEST_TABLE).unwrap();
+        assert!(raw.get("7").is_some());
     }
 }
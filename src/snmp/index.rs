@@ -0,0 +1,179 @@
+use super::{dotted, InetAddress, OctetString};
+
+use anyhow::{bail, Context, Result};
+use std::{
+    convert::TryFrom,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    slice::Iter,
+};
+
+/// A table index, decoded from the arcs that follow the column in a row's OID.
+pub trait Index: Sized {
+    fn decode(arcs: &mut Iter<'_, u32>) -> Result<Self>;
+
+    fn from_arcs(arcs: &[u32]) -> Result<Self> {
+        let mut iter = arcs.iter();
+        let value = Self::decode(&mut iter)
+            .with_context(|| format!("invalid table index: {}", dotted(arcs)))?;
+        if iter.len() != 0 {
+            bail!("unexpected arcs in table index: {}", dotted(arcs));
+        }
+
+        Ok(value)
+    }
+}
+
+fn next_arc(arcs: &mut Iter<'_, u32>) -> Result<u32> {
+    arcs.next().copied().context("table index is too short")
+}
+
+fn next_octets(arcs: &mut Iter<'_, u32>, len: usize) -> Result<Vec<u8>> {
+    (0..len)
+        .map(|_| Ok(u8::try_from(next_arc(arcs)?)?))
+        .collect()
+}
+
+/// The raw index, which takes all remaining arcs so can only come last.
+impl Index for String {
+    fn decode(arcs: &mut Iter<'_, u32>) -> Result<Self> {
+        Ok(arcs.map(u32::to_string).collect::<Vec<_>>().join("."))
+    }
+}
+
+impl Index for u32 {
+    fn decode(arcs: &mut Iter<'_, u32>) -> Result<Self> {
+        next_arc(arcs)
+    }
+}
+
+/// A variable length OCTET STRING, prefixed with its length.
+impl Index for OctetString {
+    fn decode(arcs: &mut Iter<'_, u32>) -> Result<Self> {
+        let len = next_arc(arcs)? as usize;
+        Ok(OctetString(next_octets(arcs, len)?))
+    }
+}
+
+/// An IpAddress, as four arcs.
+impl Index for Ipv4Addr {
+    fn decode(arcs: &mut Iter<'_, u32>) -> Result<Self> {
+        let octets = <[u8; 4]>::try_from(next_octets(arcs, 4)?.as_slice())?;
+        Ok(Ipv4Addr::from(octets))
+    }
+}
+
+/// An InetAddressType and InetAddress pair, with the address prefixed with its length.
+impl Index for InetAddress {
+    fn decode(arcs: &mut Iter<'_, u32>) -> Result<Self> {
+        let address_type = next_arc(arcs)?;
+        let octets = OctetString::decode(arcs)?.0;
+
+        Ok(InetAddress(match address_type {
+            1 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(octets.as_slice())?)),
+            2 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(octets.as_slice())?)),
+            _ => bail!("unsupported InetAddressType: {}", address_type),
+        }))
+    }
+}
+
+impl<A: Index, B: Index> Index for (A, B) {
+    fn decode(arcs: &mut Iter<'_, u32>) -> Result<Self> {
+        Ok((A::decode(arcs)?, B::decode(arcs)?))
+    }
+}
+
+impl<A: Index, B: Index, C: Index> Index for (A, B, C) {
+    fn decode(arcs: &mut Iter<'_, u32>) -> Result<Self> {
+        Ok((A::decode(arcs)?, B::decode(arcs)?, C::decode(arcs)?))
+    }
+}
+
+/// A composite index whose leading part can be used to join with a table indexed by just that
+/// part, e.g. `(ifIndex, SFID)` with an `ifIndex` table.
+pub trait IndexPrefix<P> {
+    fn prefix(&self) -> &P;
+}
+
+impl<A, B> IndexPrefix<A> for (A, B) {
+    fn prefix(&self) -> &A {
+        &self.0
+    }
+}
+
+impl<A, B, C> IndexPrefix<A> for (A, B, C) {
+    fn prefix(&self) -> &A {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snmp::Table;
+    use std::collections::HashMap;
+
+    #[test]
+    fn pair() {
+        assert_eq!(<(u32, u32)>::from_arcs(&[3, 42]).unwrap(), (3, 42));
+    }
+
+    #[test]
+    fn octet_string() {
+        let (octets, next) = <(OctetString, u32)>::from_arcs(&[3, 97, 98, 99, 7]).unwrap();
+        assert_eq!(octets.0, b"abc");
+        assert_eq!(next, 7);
+
+        assert!(OctetString::from_arcs(&[2, 256, 1]).is_err());
+    }
+
+    #[test]
+    fn ipv4_address() {
+        assert_eq!(
+            Ipv4Addr::from_arcs(&[192, 168, 0, 10]).unwrap(),
+            Ipv4Addr::new(192, 168, 0, 10)
+        );
+    }
+
+    #[test]
+    fn inet_address() {
+        let v4 = InetAddress::from_arcs(&[1, 4, 192, 168, 0, 10]).unwrap();
+        assert_eq!(v4.to_string(), "192.168.0.10");
+
+        let mut arcs = vec![2, 16, 0x2a, 0x01, 0x02];
+        arcs.extend([0; 12]);
+        arcs.push(1);
+        let v6 = InetAddress::from_arcs(&arcs).unwrap();
+        assert_eq!(v6.to_string(), "2a01:200::1");
+
+        // An IPv4 type with an IPv6 length, and a DNS name
+        assert!(InetAddress::from_arcs(&[1, 5, 192, 168, 0, 10, 1]).is_err());
+        assert!(InetAddress::from_arcs(&[16, 3, 97, 98, 99]).is_err());
+    }
+
+    #[test]
+    fn too_few_arcs() {
+        assert!(<(u32, u32)>::from_arcs(&[3]).is_err());
+        assert!(Ipv4Addr::from_arcs(&[192, 168, 0]).is_err());
+        assert!(OctetString::from_arcs(&[4, 97, 98]).is_err());
+    }
+
+    #[test]
+    fn leftover_arcs() {
+        assert!(u32::from_arcs(&[3, 4]).is_err());
+        assert!(Ipv4Addr::from_arcs(&[192, 168, 0, 10, 1]).is_err());
+    }
+
+    #[test]
+    fn with_prefix() {
+        let table = Table(HashMap::from([
+            ((2, 10), "a"),
+            ((2, 11), "b"),
+            ((3, 10), "c"),
+        ]));
+
+        let mut rows: Vec<_> = table.with_prefix(&2).collect();
+        rows.sort();
+        assert_eq!(rows, [(&(2, 10), &"a"), (&(2, 11), &"b")]);
+        assert_eq!(table.with_prefix(&4).count(), 0);
+    }
+}
@@ -0,0 +1,186 @@
+use anyhow::{Context, Error, Result};
+use std::{
+    borrow::Cow,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// An object identifier, held as its numeric arcs so that OIDs are ordered and prefix-matched
+/// arc by arc, e.g. `1.3.6.1.2.1.10.127.1.1.1` is not a prefix of `1.3.6.1.2.1.10.127.1.1.10`.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct OID(Cow<'static, [u32]>);
+
+/// Builds an `OID` constant from its dotted form, which is parsed at compile time.
+#[macro_export]
+macro_rules! oid {
+    ($oid:literal) => {{
+        const ARCS: [u32; $crate::snmp::arc_count($oid)] = $crate::snmp::parse_arcs($oid);
+        $crate::snmp::OID::from_static(&ARCS)
+    }};
+}
+
+pub use crate::oid;
+
+#[doc(hidden)]
+pub const fn arc_count(oid: &str) -> usize {
+    let bytes = oid.as_bytes();
+    let mut count = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'.' {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+#[doc(hidden)]
+pub const fn parse_arcs<const N: usize>(oid: &str) -> [u32; N] {
+    let bytes = oid.as_bytes();
+    let mut arcs = [0u32; N];
+    let mut arc = 0;
+    let mut digits = 0;
+    let mut i = 0;
+    while i <= bytes.len() {
+        if i == bytes.len() || bytes[i] == b'.' {
+            assert!(digits > 0, "empty arc in OID");
+            arc += 1;
+            digits = 0;
+        } else {
+            assert!(bytes[i].is_ascii_digit(), "invalid character in OID");
+            arcs[arc] = match arcs[arc].checked_mul(10) {
+                Some(value) => match value.checked_add((bytes[i] - b'0') as u32) {
+                    Some(value) => value,
+                    None => panic!("arc too large in OID"),
+                },
+                None => panic!("arc too large in OID"),
+            };
+            digits += 1;
+        }
+        i += 1;
+    }
+    arcs
+}
+
+impl OID {
+    pub const fn from_static(arcs: &'static [u32]) -> Self {
+        Self(Cow::Borrowed(arcs))
+    }
+
+    pub fn arcs(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// The OID one arc below this one, e.g. a table's entry or a scalar's instance.
+    pub fn child(&self, arc: u32) -> OID {
+        let mut arcs = self.0.to_vec();
+        arcs.push(arc);
+        OID(Cow::Owned(arcs))
+    }
+
+    /// The arcs that follow `prefix`, if this OID is under it.
+    pub fn index_after(&self, prefix: &OID) -> Option<&[u32]> {
+        self.0.strip_prefix(prefix.0.as_ref())
+    }
+}
+
+/// Formats arcs in dotted form, as for an OID or table index.
+pub fn dotted(arcs: &[u32]) -> String {
+    arcs.iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+impl Display for OID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&dotted(&self.0))
+    }
+}
+
+impl FromStr for OID {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let arcs = s
+            .split('.')
+            .map(str::parse)
+            .collect::<Result<Vec<u32>, _>>()
+            .context(format!("invalid OID: {}", s))?;
+
+        Ok(OID(Cow::Owned(arcs)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCS_IF_DOWNSTREAM_CHANNEL_TABLE: OID = oid!("1.3.6.1.2.1.10.127.1.1.1");
+
+    fn oid(s: &str) -> OID {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn prefix_matches_whole_arcs() {
+        let sibling = oid("1.3.6.1.2.1.10.127.1.1.10");
+        assert_eq!(sibling.index_after(&DOCS_IF_DOWNSTREAM_CHANNEL_TABLE), None);
+
+        let column = oid("1.3.6.1.2.1.10.127.1.1.1.1.2.3");
+        assert_eq!(
+            column.index_after(&DOCS_IF_DOWNSTREAM_CHANNEL_TABLE),
+            Some(&[1, 2, 3][..])
+        );
+        assert_eq!(
+            DOCS_IF_DOWNSTREAM_CHANNEL_TABLE.index_after(&DOCS_IF_DOWNSTREAM_CHANNEL_TABLE),
+            Some(&[][..])
+        );
+    }
+
+    #[test]
+    fn numeric_order() {
+        let mut oids = vec![oid("1.3.10"), oid("1.3.2.1"), oid("1.3.2"), oid("1.3.1.5")];
+        oids.sort();
+        assert_eq!(
+            oids,
+            [oid("1.3.1.5"), oid("1.3.2"), oid("1.3.2.1"), oid("1.3.10")]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let oid = oid("1.3.6.1.4.1.4115.1.3.4.1.9.2");
+        assert_eq!(oid.to_string(), "1.3.6.1.4.1.4115.1.3.4.1.9.2");
+        assert_eq!(oid.to_string().parse::<OID>().unwrap(), oid);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!("".parse::<OID>().is_err());
+        assert!("1..2".parse::<OID>().is_err());
+        assert!("1.a".parse::<OID>().is_err());
+    }
+
+    #[test]
+    fn child() {
+        assert_eq!(
+            DOCS_IF_DOWNSTREAM_CHANNEL_TABLE.child(1),
+            oid("1.3.6.1.2.1.10.127.1.1.1.1")
+        );
+    }
+
+    #[test]
+    fn macro_constant() {
+        assert_eq!(
+            DOCS_IF_DOWNSTREAM_CHANNEL_TABLE.arcs(),
+            [1, 3, 6, 1, 2, 1, 10, 127, 1, 1, 1]
+        );
+        assert_eq!(
+            DOCS_IF_DOWNSTREAM_CHANNEL_TABLE,
+            oid("1.3.6.1.2.1.10.127.1.1.1")
+        );
+    }
+}
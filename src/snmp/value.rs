@@ -0,0 +1,512 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::{
+    borrow::Cow,
+    convert::TryFrom,
+    fmt::{self, Display},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+    time::Duration,
+};
+
+/// An INTEGER or Integer32.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Integer(pub i32);
+
+impl FromStr for Integer {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Integer(
+            s.parse().context(format!("invalid Integer32: {}", s))?,
+        ))
+    }
+}
+
+impl From<Integer> for i64 {
+    fn from(value: Integer) -> Self {
+        value.0.into()
+    }
+}
+
+/// A TruthValue from SNMPv2-TC, where 1 is true and 2 is false.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TruthValue(pub bool);
+
+impl FromStr for TruthValue {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(TruthValue(match s {
+            "1" => true,
+            "2" => false,
+            _ => bail!("invalid TruthValue: {}", s),
+        }))
+    }
+}
+
+impl From<TruthValue> for bool {
+    fn from(value: TruthValue) -> Self {
+        value.0
+    }
+}
+
+/// An enumerated INTEGER, implemented with `#[derive(Enumeration)]`.
+pub trait Enumeration: Copy {
+    /// The syntax name, e.g. `docsIfDownChannelModulation`.
+    const NAME: &'static str;
+    /// The labels of the known values, in order.
+    const LABELS: &'static [&'static str];
+
+    fn from_code(code: i64) -> Option<Self>;
+    fn code(self) -> i64;
+    fn label(self) -> &'static str;
+}
+
+/// A value of an enumerated INTEGER, which keeps codes that `T` doesn't know, e.g. ones added by
+/// newer firmware, rather than failing to parse them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EnumValue<T> {
+    Known(T),
+    Unknown(i64),
+}
+
+impl<T: Enumeration> EnumValue<T> {
+    pub fn known(self) -> Option<T> {
+        match self {
+            EnumValue::Known(value) => Some(value),
+            EnumValue::Unknown(_) => None,
+        }
+    }
+
+    pub fn code(self) -> i64 {
+        match self {
+            EnumValue::Known(value) => value.code(),
+            EnumValue::Unknown(code) => code,
+        }
+    }
+
+    /// The label of a known value, falling back to the raw code.
+    pub fn label(self) -> Cow<'static, str> {
+        match self {
+            EnumValue::Known(value) => Cow::Borrowed(value.label()),
+            EnumValue::Unknown(code) => Cow::Owned(code.to_string()),
+        }
+    }
+}
+
+impl<T: Enumeration> FromStr for EnumValue<T> {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = i64::from(s.parse::<Integer>().context(format!(
+            "invalid {}: {}",
+            T::NAME,
+            s
+        ))?);
+
+        Ok(T::from_code(code).map_or(EnumValue::Unknown(code), EnumValue::Known))
+    }
+}
+
+impl<T: Enumeration> serde::Serialize for EnumValue<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.label())
+    }
+}
+
+/// A Counter32, which wraps at 2^32.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Counter32(pub u32);
+
+impl FromStr for Counter32 {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Counter32(
+            s.parse().context(format!("invalid Counter32: {}", s))?,
+        ))
+    }
+}
+
+impl From<Counter32> for u64 {
+    fn from(value: Counter32) -> Self {
+        value.0.into()
+    }
+}
+
+/// A Counter64.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Counter64(pub u64);
+
+impl FromStr for Counter64 {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Counter64(
+            s.parse().context(format!("invalid Counter64: {}", s))?,
+        ))
+    }
+}
+
+impl From<Counter64> for u64 {
+    fn from(value: Counter64) -> Self {
+        value.0
+    }
+}
+
+/// A Gauge32 or Unsigned32.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Gauge32(pub u32);
+
+impl FromStr for Gauge32 {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Gauge32(
+            s.parse().context(format!("invalid Gauge32: {}", s))?,
+        ))
+    }
+}
+
+impl From<Gauge32> for u64 {
+    fn from(value: Gauge32) -> Self {
+        value.0.into()
+    }
+}
+
+/// A TimeTicks, in hundredths of a second.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TimeTicks(pub u32);
+
+impl FromStr for TimeTicks {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(TimeTicks(
+            s.parse().context(format!("invalid TimeTicks: {}", s))?,
+        ))
+    }
+}
+
+impl From<TimeTicks> for Duration {
+    fn from(value: TimeTicks) -> Self {
+        Duration::from_millis(u64::from(value.0) * 10)
+    }
+}
+
+/// An OCTET STRING, which the hub sends as `$` followed by hex digits when it isn't printable.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OctetString(pub Vec<u8>);
+
+impl OctetString {
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.0).into_owned()
+    }
+}
+
+impl FromStr for OctetString {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = match s.strip_prefix('$') {
+            Some(hex) => hex,
+            None => return Ok(OctetString(s.as_bytes().to_vec())),
+        };
+
+        // Checked up front, as slicing by byte offset would panic part way into a multi-byte char
+        if hex.len() % 2 != 0 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            bail!("invalid hex octet string: {}", s);
+        }
+
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                u8::from_str_radix(&hex[i..i + 2], 16)
+                    .context(format!("invalid hex octet string: {}", s))
+            })
+            .collect::<Result<_>>()
+            .map(OctetString)
+    }
+}
+
+impl From<OctetString> for Vec<u8> {
+    fn from(value: OctetString) -> Self {
+        value.0
+    }
+}
+
+impl From<OctetString> for String {
+    fn from(value: OctetString) -> Self {
+        value.to_string_lossy()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MacAddress([u8; 6]);
+
+impl FromStr for MacAddress {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = if s.starts_with('$') {
+            OctetString::from_str(s)?.0
+        } else {
+            s.split([':', '-'])
+                .map(|octet| u8::from_str_radix(octet, 16))
+                .collect::<Result<_, _>>()
+                .context(format!("invalid MAC address: {}", s))?
+        };
+
+        let bytes = <[u8; 6]>::try_from(bytes.as_slice())
+            .map_err(|_| anyhow!("invalid MAC address: {}", s))?;
+        Ok(MacAddress(bytes))
+    }
+}
+
+impl Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            a, b, c, d, e, g
+        )
+    }
+}
+
+/// An InetAddress or IpAddress, sent either as text or as 4 or 16 hex-encoded octets. An empty
+/// address, which goes with InetAddressType unknown(0), reads as the unspecified address.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct InetAddress(pub IpAddr);
+
+impl FromStr for InetAddress {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s == "$" {
+            return Ok(InetAddress(IpAddr::V4(Ipv4Addr::UNSPECIFIED)));
+        }
+        if !s.starts_with('$') {
+            return Ok(InetAddress(
+                s.parse().context(format!("invalid InetAddress: {}", s))?,
+            ));
+        }
+
+        let bytes = OctetString::from_str(s)?.0;
+        if let Ok(bytes) = <[u8; 4]>::try_from(bytes.as_slice()) {
+            Ok(InetAddress(IpAddr::V4(Ipv4Addr::from(bytes))))
+        } else if let Ok(bytes) = <[u8; 16]>::try_from(bytes.as_slice()) {
+            Ok(InetAddress(IpAddr::V6(Ipv6Addr::from(bytes))))
+        } else {
+            bail!("invalid InetAddress: {}", s)
+        }
+    }
+}
+
+impl Display for InetAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+/// A BITS value, where bit 0 is the most significant bit of the first octet.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Bits(Vec<u8>);
+
+impl Bits {
+    pub fn is_set(&self, bit: usize) -> bool {
+        self.0
+            .get(bit / 8)
+            .is_some_and(|octet| octet & (0x80 >> (bit % 8)) != 0)
+    }
+}
+
+impl FromStr for Bits {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Bits(OctetString::from_str(s)?.0))
+    }
+}
+
+/// A DateAndTime from SNMPv2-TC, which is all zeros when the hub hasn't set it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DateAndTime(Option<i64>);
+
+impl DateAndTime {
+    /// Seconds since the Unix epoch, or `None` if unset.
+    pub fn timestamp(&self) -> Option<i64> {
+        self.0
+    }
+}
+
+impl FromStr for DateAndTime {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = OctetString::from_str(s)?.0;
+        if bytes.len() != 8 && bytes.len() != 11 {
+            bail!("invalid DateAndTime: {}", s);
+        }
+
+        let year = i64::from(u16::from_be_bytes([bytes[0], bytes[1]]));
+        let (month, day) = (i64::from(bytes[2]), i64::from(bytes[3]));
+        if month == 0 && day == 0 {
+            return Ok(DateAndTime(None));
+        }
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            bail!("invalid DateAndTime: {}", s);
+        }
+
+        // Days since the epoch for the proleptic Gregorian calendar
+        let y = if month <= 2 { year - 1 } else { year };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146097 + doe - 719468;
+
+        let mut timestamp = days * 86400
+            + i64::from(bytes[4]) * 3600
+            + i64::from(bytes[5]) * 60
+            + i64::from(bytes[6]);
+
+        // The 8 byte form has no timezone. The hub's clock is set from a DOCSIS time server,
+        // which gives UTC, so it's read as UTC.
+        if bytes.len() == 11 {
+            let offset = i64::from(bytes[9]) * 3600 + i64::from(bytes[10]) * 60;
+            match bytes[8] {
+                b'+' => timestamp -= offset,
+                b'-' => timestamp += offset,
+                _ => bail!("invalid DateAndTime: {}", s),
+            }
+        }
+
+        Ok(DateAndTime(Some(timestamp)))
+    }
+}
+
+/// Converts a fixed-point column to its natural unit, e.g. a power in tenths of a dBmV.
+pub trait Scale<T> {
+    fn scale(self, divisor: u32) -> T;
+}
+
+impl Scale<f64> for i64 {
+    fn scale(self, divisor: u32) -> f64 {
+        self as f64 / f64::from(divisor)
+    }
+}
+
+impl Scale<f64> for u64 {
+    fn scale(self, divisor: u32) -> f64 {
+        self as f64 / f64::from(divisor)
+    }
+}
+
+impl Scale<u64> for u64 {
+    fn scale(self, divisor: u32) -> u64 {
+        self / u64::from(divisor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn octet_string_from_text() {
+        let octet_string: OctetString = "nas".parse().unwrap();
+        assert_eq!(octet_string.0, b"nas");
+    }
+
+    #[test]
+    fn octet_string_from_hex() {
+        let octet_string: OctetString = "$6E6173".parse().unwrap();
+        assert_eq!(octet_string.0, b"nas");
+
+        let empty: OctetString = "$".parse().unwrap();
+        assert!(empty.0.is_empty());
+    }
+
+    #[test]
+    fn octet_string_invalid_hex() {
+        assert!("$6e617".parse::<OctetString>().is_err());
+        assert!("$6g".parse::<OctetString>().is_err());
+        assert!("$+1".parse::<OctetString>().is_err());
+        assert!("$aé0".parse::<OctetString>().is_err());
+    }
+
+    #[test]
+    fn mac_address() {
+        let hex: MacAddress = "$001122AABBCC".parse().unwrap();
+        let colons: MacAddress = "00:11:22:aa:bb:cc".parse().unwrap();
+        let dashes: MacAddress = "00-11-22-AA-BB-CC".parse().unwrap();
+
+        assert_eq!(hex, colons);
+        assert_eq!(hex, dashes);
+        assert_eq!(hex.to_string(), "00:11:22:aa:bb:cc");
+    }
+
+    #[test]
+    fn mac_address_wrong_length() {
+        assert!("$001122aabb".parse::<MacAddress>().is_err());
+        assert!("00:11:22:aa:bb:cc:dd".parse::<MacAddress>().is_err());
+    }
+
+    #[test]
+    fn inet_address() {
+        let text: InetAddress = "192.168.0.10".parse().unwrap();
+        let v4: InetAddress = "$c0a8000a".parse().unwrap();
+        assert_eq!(text, v4);
+        assert_eq!(v4.to_string(), "192.168.0.10");
+
+        let v6: InetAddress = "$2a010200000000000000000000000001".parse().unwrap();
+        assert_eq!(v6.to_string(), "2a01:200::1");
+    }
+
+    #[test]
+    fn inet_address_empty() {
+        let empty: InetAddress = "$".parse().unwrap();
+        assert!(empty.0.is_unspecified());
+        assert_eq!("".parse::<InetAddress>().unwrap(), empty);
+    }
+
+    #[test]
+    fn inet_address_wrong_length() {
+        assert!("$c0a800".parse::<InetAddress>().is_err());
+        assert!("not an address".parse::<InetAddress>().is_err());
+    }
+
+    #[test]
+    fn date_and_time_without_timezone() {
+        // 2026-10-18 10:30:00, read as UTC
+        let date_and_time: DateAndTime = "$07ea0a120a1e0000".parse().unwrap();
+        assert_eq!(date_and_time.timestamp(), Some(1_792_319_400));
+    }
+
+    #[test]
+    fn date_and_time_with_timezone() {
+        // 2026-10-18 21:02:08 +00:00
+        let utc: DateAndTime = "$07ea0a12150208002b0000".parse().unwrap();
+        assert_eq!(utc.timestamp(), Some(1_792_357_328));
+
+        // 2026-10-19 08:30:00 +01:00 and -05:30
+        let ahead: DateAndTime = "$07ea0a13081e00002b0100".parse().unwrap();
+        assert_eq!(ahead.timestamp(), Some(1_792_395_000));
+        let behind: DateAndTime = "$07ea0a13081e00002d051e".parse().unwrap();
+        assert_eq!(behind.timestamp(), Some(1_792_418_400));
+    }
+
+    #[test]
+    fn date_and_time_unset() {
+        let unset: DateAndTime = "$0000000000000000".parse().unwrap();
+        assert_eq!(unset.timestamp(), None);
+    }
+
+    #[test]
+    fn date_and_time_invalid() {
+        assert!("$07ea0a12".parse::<DateAndTime>().is_err());
+        assert!("$07ea0d120a1e0000".parse::<DateAndTime>().is_err());
+        assert!("$07ea0a120a1e00002a0000".parse::<DateAndTime>().is_err());
+    }
+}
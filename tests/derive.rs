@@ -0,0 +1,54 @@
+//! Derives a table row and an enumeration outside the exporter's crate, with `#[snmp(crate = "...")]`.
+
+use std::rc::Rc;
+use virgin_media_prometheus_exporter::{
+    oid,
+    snmp::{EnumValue, Enumeration, List, Table, TableRow, OID},
+};
+
+const DOCS_IF_DOWNSTREAM_CHANNEL_TABLE: OID = oid!("1.3.6.1.2.1.10.127.1.1.1"); // docsIfDownstreamChannelTable
+const DOCS_IF_DOWN_CHANNEL_MODULATION: OID = oid!("1.3.6.1.2.1.10.127.1.1.1.1.4"); // docsIfDownChannelModulation
+const DOCS_IF_DOWN_CHANNEL_POWER: OID = oid!("1.3.6.1.2.1.10.127.1.1.1.1.6"); // docsIfDownChannelPower
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Enumeration)]
+#[snmp(
+    crate = "virgin_media_prometheus_exporter",
+    name = "docsIfDownChannelModulation"
+)]
+enum DownstreamModulation {
+    QAM64 = 3,
+    QAM256 = 4,
+}
+
+#[derive(Debug, TableRow)]
+#[snmp(crate = "virgin_media_prometheus_exporter")]
+struct DownstreamChannel {
+    #[snmp(column = DOCS_IF_DOWN_CHANNEL_MODULATION)]
+    modulation: EnumValue<DownstreamModulation>,
+    #[snmp(column = DOCS_IF_DOWN_CHANNEL_POWER, kind = integer, scale = 10)]
+    power: f64,
+}
+
+#[test]
+fn derives_outside_the_crate() {
+    let router_status = List::from_reader(
+        r#"{
+            "1.3.6.1.2.1.10.127.1.1.1.1.4.3": "4",
+            "1.3.6.1.2.1.10.127.1.1.1.1.6.3": "-25"
+        }"#
+        .as_bytes(),
+        &[DOCS_IF_DOWNSTREAM_CHANNEL_TABLE],
+    )
+    .unwrap();
+    let table: Rc<Table<DownstreamChannel>> = router_status
+        .parse_table(&DOCS_IF_DOWNSTREAM_CHANNEL_TABLE)
+        .unwrap();
+
+    let channel = table.get("3").unwrap();
+    assert_eq!(
+        channel.modulation.known(),
+        Some(DownstreamModulation::QAM256)
+    );
+    assert_eq!(channel.modulation.label(), "qam256");
+    assert_eq!(channel.power, -2.5);
+}